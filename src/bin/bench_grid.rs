@@ -0,0 +1,83 @@
+// Collision benchmark
+// Times a frame's worth of projectile-vs-enemy checks, once by testing every pair and once through the SpatialGrid,
+// with enemies and projectiles scattered across the screen
+//
+// Usage: cargo run --release --bin bench_grid -- [frames]
+use space_shooter::model::*;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let frames: u32 = std::env::args()
+        .nth(1)
+        .map_or(1000, |arg| arg.parse().expect("frames must be a number"));
+    let platform = MockPlatform::new(1, vec![]);
+    let (screen_w, screen_h) = platform.resolution();
+    let definitions = EnemyDefinition::load().unwrap_or_else(|err| panic!("invalid data/enemies.json: {}", err));
+    let mut rng = GameRng::new(1);
+
+    println!("Collision checks per frame, averaged over {} frames", frames);
+    println!("  {:>8} {:>11} {:>10} {:>10} {:>8}", "enemies", "projectiles", "pairs", "grid", "speedup");
+    for count in [25, 50, 100, 200, 400, 800] {
+        // enemies anywhere on screen, with 4 projectiles in flight for every enemy
+        let enemies: Vec<Enemy> = (0..count)
+            .map(|_| {
+                let definition = EnemyDefinition::pick(&definitions, &mut rng);
+                let mut enemy = Enemy::new(definition, &mut rng, &platform);
+                enemy.hitbox.y = (rng.spawns.u32() % screen_h) as f32;
+                enemy
+            })
+            .collect();
+        let projectiles: Vec<Collider> = (0..count * 4)
+            .map(|_| {
+                let hitbox = Hitbox {
+                    x: (rng.spawns.u32() % screen_w) as f32,
+                    y: (rng.spawns.u32() % screen_h) as f32,
+                    w: 6,
+                    h: 6,
+                };
+                Collider::new(&hitbox, Shape::Circle)
+            })
+            .collect();
+
+        // every projectile against every enemy
+        let start = Instant::now();
+        let mut pair_hits = 0;
+        for _ in 0..frames {
+            for projectile in &projectiles {
+                pair_hits += enemies.iter().filter(|enemy| enemy.collides(projectile)).count();
+            }
+        }
+        let pairs = start.elapsed();
+
+        // building the grid, then only checking the enemies in the cells each projectile covers
+        let mut grid = SpatialGrid::new(&platform);
+        let start = Instant::now();
+        let mut grid_hits = 0;
+        for _ in 0..frames {
+            grid.build(enemies.iter().map(|enemy| &enemy.hitbox));
+            for projectile in &projectiles {
+                grid_hits += grid
+                    .query(&projectile.bounds())
+                    .into_iter()
+                    .filter(|&index| enemies[index].collides(projectile))
+                    .count();
+            }
+        }
+        let gridded = start.elapsed();
+        assert_eq!(pair_hits, grid_hits, "the grid missed or invented collisions");
+
+        println!(
+            "  {:>8} {:>11} {:>10} {:>10} {:>7.1}x",
+            count,
+            count * 4,
+            per_frame(pairs, frames),
+            per_frame(gridded, frames),
+            pairs.as_secs_f64() / gridded.as_secs_f64()
+        );
+    }
+}
+
+// Average time per frame, formatted in microseconds
+fn per_frame(total: Duration, frames: u32) -> String {
+    format!("{:.1}us", total.as_secs_f64() * 1_000_000.0 / frames as f64)
+}
@@ -0,0 +1,173 @@
+// Headless balance simulator
+// Plays many runs of the game with the autopilot and prints statistics used for tuning,
+// e.g. the spawn curve in GameState::spawn_enemies and the drop tables in Powerup::new_random
+//
+// Usage: cargo run --release --bin simulate -- [runs] [seed] [level]
+use space_shooter::model::*;
+use space_shooter::{GameState, Scene};
+
+const MAX_FRAMES: u32 = 60 * 60 * 10; // runs still going after 10 minutes are stopped and counted as survived
+
+// Plays a single run of the given level from the menu until the player dies or clears the level, returning its statistics
+fn simulate(seed: u32, level: usize) -> RunStats {
+    let mut platform = MockPlatform::new(seed, vec![]);
+    let mut state = GameState::with_platform(&mut platform);
+    state.level_index = level;
+    let mut autopilot = Autopilot::new();
+    while state.scenes.top() != Scene::GameOver
+    && state.players[0].run_stats.frames < MAX_FRAMES
+    && !(state.level.finished() && state.enemies.is_empty()) {
+        // feed the autopilot's input to the platform one frame at a time
+        let frame = autopilot.input(&state.players[0], &state.enemies, &state.projectiles, &state.powerups, &platform);
+        platform.script = vec![frame];
+        platform.frame = 0;
+        platform.sounds.clear();
+        state.step(&mut platform);
+    }
+    state.players.remove(0).run_stats
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let runs: u32 = args.next().map_or(1000, |arg| arg.parse().ok().filter(|&runs| runs > 0).expect("runs must be a number above 0"));
+    let seed: u32 = args.next().map_or(1, |arg| arg.parse().expect("seed must be a number"));
+    // Levels are picked by name, case insensitive, defaulting to the first one
+    let levels = GameState::with_platform(&mut MockPlatform::new(0, vec![])).levels;
+    let level = args.next().map_or(0, |name| {
+        levels
+            .iter()
+            .position(|level| level.name.eq_ignore_ascii_case(&name))
+            .unwrap_or_else(|| panic!("unknown level {}", name))
+    });
+
+    // Split the runs across threads, every run is independent
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    let results: Vec<RunStats> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                scope.spawn(move || {
+                    (t..runs)
+                        .step_by(threads as usize)
+                        .map(|i| simulate(seed.wrapping_add(i), level))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    });
+
+    println!(
+        "Simulated {} runs of {}, seeds {}..{}",
+        runs,
+        levels[level].name,
+        seed,
+        seed.wrapping_add(runs)
+    );
+    print_survival(&results);
+    print_score_curve(&results);
+    print_deaths(&results);
+    print_powerups(&results);
+}
+
+fn print_survival(results: &[RunStats]) {
+    let mut seconds: Vec<f32> = results.iter().map(|run| run.frames as f32 / 60.0).collect();
+    seconds.sort_by(f32::total_cmp);
+    let percentile = |p: f32| seconds[((seconds.len() - 1) as f32 * p) as usize];
+    let mean = seconds.iter().sum::<f32>() / seconds.len() as f32;
+    println!("\nSurvival time (seconds)");
+    println!(
+        "  min {:.1}  p25 {:.1}  median {:.1}  p75 {:.1}  max {:.1}  mean {:.1}",
+        percentile(0.0),
+        percentile(0.25),
+        percentile(0.5),
+        percentile(0.75),
+        percentile(1.0),
+        mean
+    );
+    // histogram in 30 second buckets
+    let buckets = (percentile(1.0) / 30.0) as usize + 1;
+    let mut counts = vec![0; buckets];
+    for s in &seconds {
+        counts[(s / 30.0) as usize] += 1;
+    }
+    let most = counts.iter().copied().max().unwrap_or(1);
+    for (i, count) in counts.iter().enumerate() {
+        println!(
+            "  {:>10} {:<40} {}",
+            format!("{}-{}s", i * 30, (i + 1) * 30),
+            "#".repeat(count * 40 / most),
+            count
+        );
+    }
+}
+
+fn print_score_curve(results: &[RunStats]) {
+    println!("\nScore over time (runs still alive at each mark)");
+    println!("  {:>6} {:>6} {:>8} {:>8}", "time", "alive", "mean", "median");
+    let samples = results.iter().map(|run| run.score_curve.len()).max().unwrap_or(0);
+    // the curve is sampled every 10 seconds, print every 30
+    for i in (2..samples).step_by(3) {
+        let mut scores: Vec<u32> = results.iter().filter_map(|run| run.score_curve.get(i).copied()).collect();
+        scores.sort();
+        let mean = scores.iter().sum::<u32>() as f32 / scores.len() as f32;
+        println!(
+            "  {:>5}s {:>6} {:>8.0} {:>8}",
+            (i + 1) * 10,
+            scores.len(),
+            mean,
+            scores[scores.len() / 2]
+        );
+    }
+}
+
+fn print_deaths(results: &[RunStats]) {
+    println!("\nDeaths by enemy type");
+    let mut deaths: Vec<(String, u32)> = vec![];
+    for run in results {
+        let cause = match &run.killed_by {
+            Some(enemy_type) => enemy_type.clone(),
+            None => "(survived)".to_string(),
+        };
+        tally(&mut deaths, &cause);
+    }
+    deaths.sort_by_key(|death| std::cmp::Reverse(death.1));
+    for (cause, count) in deaths {
+        println!(
+            "  {:<12} {:>6} {:>5.1}%",
+            cause,
+            count,
+            count as f32 * 100.0 / results.len() as f32
+        );
+    }
+}
+
+fn print_powerups(results: &[RunStats]) {
+    println!("\nPowerups");
+    println!("  {:<26} {:>8} {:>8} {:>8} {:>7}", "effect", "dropped", "per run", "picked", "rate");
+    let mut totals: Vec<(PowerupEffect, u32, u32)> = vec![];
+    for run in results {
+        for (effect, count) in &run.drops {
+            match totals.iter_mut().find(|(e, _, _)| e == effect) {
+                Some(total) => total.1 += count,
+                None => totals.push((effect.clone(), *count, 0)),
+            }
+        }
+        for (effect, count) in &run.pickups {
+            match totals.iter_mut().find(|(e, _, _)| e == effect) {
+                Some(total) => total.2 += count,
+                None => totals.push((effect.clone(), 0, *count)),
+            }
+        }
+    }
+    totals.sort_by_key(|total| std::cmp::Reverse(total.1));
+    for (effect, dropped, picked) in totals {
+        println!(
+            "  {:<26} {:>8} {:>8.2} {:>8} {:>6.1}%",
+            format!("{:?}", effect),
+            dropped,
+            dropped as f32 / results.len() as f32,
+            picked,
+            picked as f32 * 100.0 / dropped.max(1) as f32
+        );
+    }
+}
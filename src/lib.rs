@@ -1,64 +1,259 @@
 use turbo::*;
-mod model;
+pub mod model;
 use model::*;
 
-// Different scenes in the game handled by a state machine
+// Different scenes in the game, kept on a SceneStack so overlays can sit on top of the scene they were opened from
 #[turbo::serialize]
-#[derive(PartialEq)]
+#[derive(PartialEq, Copy)]
 pub enum Scene {
     Menu,
     Game,
     GameOver,
+    Paused, // game frozen under the pause menu
+    NameEntry, // entering initials for a new high score
+    HighScores, // high score table, from the menu or after entering initials
+    Options, // settings, from the menu or the pause menu
+    Controls, // rebinding the controls, from the options
+}
+
+impl Scene {
+    // Overlays are drawn on top of the scene under them instead of replacing it
+    pub fn is_overlay(&self) -> bool {
+        matches!(self, Scene::Paused | Scene::NameEntry | Scene::Options | Scene::Controls)
+    }
 }
 
 // The [turbo::game] macro sets up the game loop.
 #[turbo::game]
 // It expects a GameState struct with a new() and update(&mut self) method, which is executed once per frame.
 // All game logic and rendering is handled within the update method, so every stored entity must be updated and drawn in this scope.
-struct GameState {
+pub struct GameState {
     // Game management variables
-    scene: Scene,
-    tick: u32, // using our own tick counter for Scene management, able to reset to 0
+    pub scenes: SceneStack, // open scenes, only the top one is updated
+    pub tick: u32, // using our own tick counter for Scene management, able to reset to 0
+    pub rng: GameRng, // seeded random number generator, all gameplay randomness goes through it so runs can be reproduced
+    pub controls: [Input; MAX_PLAYERS], // input from the platform's controls, one per gamepad, menus only read the first
+    pub inputs: Vec<Actions>, // this frame's actions for each player as seen by the game, from the controls or the replay being watched
+    pub replay: Replay, // recording of the run in progress
+    pub playback: Option<Replay>, // replay being watched, None while playing normally
+    pub last_replay: Option<Replay>, // recording of the last finished run, can be watched from the menu
+    pub autopilot: Option<Autopilot>, // bot playing the attract mode demo, None while a person is playing
+    pub enemy_definitions: Vec<EnemyDefinition>, // enemy archetypes loaded from data/enemies.json
+    pub levels: Vec<Level>, // level scripts loaded from data/levels/
+    pub level_index: usize, // level selected on the menu
+    pub level: LevelRunner, // level script being played
+    pub grids: Grids, // where enemies and powerups are, rebuilt every frame to speed up collision checks
+    pub save: SaveData, // high scores and settings, loaded from the platform's storage
+    pub name_entry: NameEntry, // initials being entered for a new high score
+    pub new_high_score: Option<usize>, // position of the score just entered, highlighted on the table
+    pub pause_menu: PauseMenu,
+    pub options_menu: OptionsMenu,
+    pub controls_menu: ControlsMenu,
+    pub difficulty: Difficulty, // difficulty of the run being played, from the settings or the replay being watched
     
     // Game entities
     // To keep the project organized, these entities are defined in separate files within the model/ directory
-    hud: HUD,
-    player: Player,
-    enemies: Vec<Enemy>,
-    projectiles: Vec<Projectile>,
-    powerups: Vec<Powerup>,
+    pub hud: HUD,
+    pub players: Vec<Player>, // the first player, then the partner once they drop in on the second gamepad
+    pub enemies: Vec<Enemy>,
+    pub projectiles: Vec<Projectile>,
+    pub powerups: Vec<Powerup>,
 }
 
 impl GameState {
     // Req. for [turbo::game] macro
     // Initialize a new game state
     fn new() -> Self {
+        Self::with_platform(&mut TurboPlatform::new(&Settings::default()))
+    }
+    // Req. for [turbo::game] macro
+    // Update the game state each frame
+    fn update(&mut self) {
+        // Drawing all game elements, including player, enemies, environment, and UI
+        self.draw_game_elements();
+        // Running the game logic
+        let mut platform = TurboPlatform::new(&self.save.settings);
+        self.step(&mut platform);
+    }
+
+    // Initialize a new game state on the given platform
+    pub fn with_platform(platform: &mut dyn Platform) -> Self {
+        let enemy_definitions = EnemyDefinition::load()
+            .unwrap_or_else(|err| panic!("invalid data/enemies.json: {}", err));
+        let levels = Level::load(&enemy_definitions)
+            .unwrap_or_else(|err| panic!("invalid level script: {}", err));
         Self {
             // Initialize all fields with default values
-            scene: Scene::Menu,
+            scenes: SceneStack::new(Scene::Menu),
             tick: 0,
+            rng: GameRng::new(platform.entropy()),
+            controls: Default::default(),
+            inputs: vec![Actions::default()],
+            replay: Replay::new(0, 0, Difficulty::Normal),
+            playback: None,
+            last_replay: None,
+            autopilot: None,
+            level: LevelRunner::new(levels[0].clone()),
+            level_index: 0,
+            levels,
+            enemy_definitions,
+            grids: Grids::new(platform),
+            save: SaveData::load(platform),
+            name_entry: NameEntry::new(),
+            new_high_score: None,
+            pause_menu: PauseMenu::new(),
+            options_menu: OptionsMenu::new(),
+            controls_menu: ControlsMenu::new(),
+            difficulty: Difficulty::Normal,
             
             hud: HUD::new(),
-            player: Player::new(),
+            players: vec![Player::new(0, platform)],
             enemies: vec![],
             projectiles: vec![],
             powerups: vec![],
         }
     }
-    // Req. for [turbo::game] macro
-    // Update the game state each frame
-    fn update(&mut self) {
-        // Drawing all game elements, including player, enemies, environment, and UI
-        self.draw_game_elements();
-        // State machine for the game scene, updating game state contextually
-        match self.scene {
+    // Advance the game logic by one frame, without drawing anything
+    // All interaction with the outside world goes through the platform, so this can also run headless
+    pub fn step(&mut self, platform: &mut dyn Platform) {
+        // Read this frame's input, from the replay or the autopilot while they are playing and from the controls otherwise
+        for (controls, frame) in self.controls.iter_mut().zip(platform.input()) {
+            controls.update(frame);
+        }
+        // Transitions cover the screen before the scenes change, and nothing is updated until they have
+        if let Some(change) = self.scenes.update() {
+            self.change_scene(change, platform);
+        }
+        if self.scenes.blocked() {
+            return;
+        }
+        // Pause pauses a run being played, from any player's gamepad
+        // Checked before the game sees this frame, and the game's input is left alone while paused,
+        // so a replay of the run lines up the frames before and after the pause the same way
+        if self.scenes.top() == Scene::Game
+            && self.players.iter().any(|player| player.hp > 0)
+            && self.playback.is_none()
+            && self.autopilot.is_none()
+            && (0..self.players.len()).any(|i| self.controls[i].just_pressed(self.save.settings.bindings(i).button(Action::Pause)))
+        {
+            self.scenes.push(Scene::Paused);
+            self.pause_menu = PauseMenu::new();
+            platform.remove_shake();
+            return;
+        }
+        // Menus that can be opened over a run read the controls, leaving the game's input alone as well
+        match self.scenes.top() {
+            Scene::Paused => {
+                self.update_pause_menu(platform);
+                return;
+            }
+            Scene::Options => {
+                // Settings are saved once the menu is closed, they already apply while it's open
+                match self.options_menu.update(&self.controls[0], &mut self.save.settings, platform) {
+                    Some(OptionsItem::Controls) => {
+                        self.controls_menu = ControlsMenu::new();
+                        self.scenes.push(Scene::Controls);
+                    }
+                    Some(_) => {
+                        self.save.save(platform);
+                        self.scenes.pop();
+                    }
+                    None => {}
+                }
+                return;
+            }
+            // Every gamepad's controls are rebound from the first one's menu, they're saved along with the options
+            Scene::Controls => {
+                if self.controls_menu.update(&self.controls, &mut self.save.settings, platform) {
+                    self.scenes.pop();
+                }
+                return;
+            }
+            _ => {}
+        }
+        // Menus read the controls directly, but the game only sees actions, through each gamepad's bindings
+        let settings = &self.save.settings;
+        let frames = match (&mut self.playback, &mut self.autopilot) {
+            _ if self.scenes.top() != Scene::Game => (0..self.players.len()).map(|i| settings.bindings(i).map(&self.controls[i].frame)).collect(),
+            (Some(replay), _) => replay.next_frame().unwrap_or_default(),
+            // The autopilot presses buttons as if the controls were never rebound
+            (None, Some(autopilot)) => vec![Bindings::default().map(&autopilot.input(&self.players[0], &self.enemies, &self.projectiles, &self.powerups, platform))],
+            // Auto-fire holds fire down, before the frame is recorded so replays don't depend on the setting
+            // A partner drops in by pressing fire or pause on the next gamepad, their actions are read from that frame on
+            (None, None) => {
+                let next = self.players.len();
+                let joining = next < MAX_PLAYERS && {
+                    let bindings = settings.bindings(next);
+                    self.controls[next].just_pressed(bindings.button(Action::Fire)) || self.controls[next].just_pressed(bindings.button(Action::Pause))
+                };
+                (0..next + usize::from(joining))
+                    .map(|i| {
+                        let mut frame = settings.bindings(i).map(&self.controls[i].frame);
+                        if settings.auto_fire {
+                            frame.set(Action::Fire, true);
+                        }
+                        frame
+                    })
+                    .collect()
+            }
+        };
+        // Partners join once there are actions for them, which is also how they join on a replay
+        while self.players.len() < frames.len() {
+            let mut partner = Player::new(self.players.len(), platform);
+            partner.set_max_hp(self.difficulty.player_hp());
+            self.players[0].notifications.push(format!("PLAYER {} JOINED!", partner.index + 1));
+            self.players.push(partner);
+            self.inputs.push(Actions::default());
+        }
+        for (i, input) in self.inputs.iter_mut().enumerate() {
+            input.update(frames.get(i).cloned().unwrap_or_default());
+        }
+        // State machine for the top scene, updating game state contextually
+        let scene = self.scenes.top();
+        match scene {
             // Main menu scene
             Scene::Menu => {
-                // Draw menu
-                self.hud.draw_menu(self.tick);
                 // Start game on button press
-                if gamepad::get(0).start.just_pressed() || gamepad::get(0).a.just_pressed() {
-                    self.scene = Scene::Game; // transition scene
+                if self.start_pressed() {
+                    self.start_game();
+                    self.scenes.transition(TransitionEffect::Fade, SceneChange::Replace(Scene::Game));
+                }
+                // Watch the last run, replaying its input from the same seed
+                else if self.controls[0].just_pressed(Button::Select) || self.controls[0].just_pressed(Button::Y) {
+                    if let Some(mut replay) = self.last_replay.clone() {
+                        replay.rewind();
+                        self.rng = GameRng::new(replay.seed);
+                        self.level = LevelRunner::new(self.levels[replay.level].clone());
+                        self.difficulty = replay.difficulty;
+                        self.players[0].set_max_hp(self.difficulty.player_hp());
+                        self.playback = Some(replay);
+                        self.scenes.transition(TransitionEffect::Fade, SceneChange::Replace(Scene::Game));
+                    }
+                }
+                // Look at the high score table
+                else if self.controls[0].just_pressed(Button::X) {
+                    self.scenes.transition(TransitionEffect::Wipe, SceneChange::Push(Scene::HighScores));
+                }
+                else if self.controls[0].just_pressed(Button::B) {
+                    self.options_menu = OptionsMenu::new();
+                    self.scenes.push(Scene::Options);
+                }
+                // Play the attract mode demo after 10 seconds without input
+                else if self.tick >= 60 * 10 {
+                    self.autopilot = Some(Autopilot::new());
+                    self.rng = GameRng::new(platform.entropy());
+                    self.level = LevelRunner::new(self.levels[0].clone());
+                    self.scenes.transition(TransitionEffect::Iris, SceneChange::Replace(Scene::Game));
+                }
+                // Choose a level
+                let count = self.levels.len();
+                if self.controls[0].just_pressed(Button::Left) {
+                    self.level_index = (self.level_index + count - 1) % count;
+                }
+                if self.controls[0].just_pressed(Button::Right) {
+                    self.level_index = (self.level_index + 1) % count;
+                }
+                if self.controls[0].frame != InputFrame::default() {
                     self.tick = 0;
                 }
                 // Increment tick counter for menu animations
@@ -66,132 +261,370 @@ impl GameState {
             }
             // Game and Game Over scenes
             Scene::Game | Scene::GameOver => { 
-                // Update the player, or prompt for restart if dead
-                if self.player.hp > 0 {
-                    self.player.update(&mut self.projectiles, &mut self.powerups, &mut self.enemies);
+                // Any input ends the demo, and so does the autopilot dying
+                // Fire or pause, on any joined player's bindings, goes on to play, anything else back to the menu
+                if self.autopilot.is_some() {
+                    if self.start_pressed() {
+                        self.scenes.transition(TransitionEffect::Fade, SceneChange::Retry);
+                        return;
+                    }
+                    if self.controls[0].frame != InputFrame::default() || self.players[0].hp == 0 {
+                        self.scenes.transition(TransitionEffect::Fade, SceneChange::Reset);
+                        return;
+                    }
+                }
+                else if self.playback.is_none() && scene == Scene::Game {
+                    self.replay.record(&frames);
+                }
+                // Leave a replay early, checking the controls since the replay supplies the input
+                else if self.playback.is_some() && scene == Scene::Game && self.controls[0].just_pressed(Button::Select) {
+                    self.scenes.transition(TransitionEffect::Fade, SceneChange::Reset);
+                    return;
+                }
+
+                // Update the players, or prompt for restart once they're all down
+                if self.players.iter().any(|player| player.hp > 0) {
+                    for (player, input) in self.players.iter_mut().zip(&self.inputs) {
+                        if player.hp > 0 {
+                            let count = self.powerups.len();
+                            player.update(input, &self.save.settings.bindings(player.index), &mut self.projectiles, &mut self.powerups, &mut self.enemies, &self.grids, &mut self.rng, platform);
+                            // The grid holds indices into the powerups, so it's rebuilt for the next player once any are picked up
+                            if self.powerups.len() != count {
+                                self.grids.powerups.build(self.powerups.iter().map(|powerup| &powerup.hitbox));
+                            }
+                        }
+                    }
+                    self.revive_players(platform);
                 } else {
-                    self.scene = Scene::GameOver;
-                    if self.player.reset() {
-                        *self = Self::new(); // reset entire game state to initial value
+                    // Keep the recording of the run that just ended
+                    if scene == Scene::Game && self.playback.is_none() {
+                        self.last_replay = Some(self.replay.clone());
+                    }
+                    if scene == Scene::Game {
+                        self.scenes.replace(Scene::GameOver);
+                    }
+                    // any player can go on, and all of them have to be run so their hit timers keep counting down
+                    let mut reset = false;
+                    for (player, input) in self.players.iter_mut().zip(&self.inputs) {
+                        reset |= player.reset(input, platform);
+                    }
+                    if reset {
+                        // Runs good enough for the high score table go on to enter initials, demos and replays don't count
+                        // A co-op run counts the players' scores together
+                        if self.playback.is_none() && self.save.high_scores.qualifies(self.score()) {
+                            self.scenes.push(Scene::NameEntry);
+                            self.name_entry = NameEntry::new();
+                        } else {
+                            self.scenes.transition(TransitionEffect::Iris, SceneChange::Reset);
+                        }
+                        return;
                     }
                 }
                 
-                // Update the HUD
-                self.hud.update(&mut self.player);
+                // Update the HUD, the partner's notifications are shown along with the first player's
+                for partner in 1..self.players.len() {
+                    let notifications = std::mem::take(&mut self.players[partner].notifications);
+                    self.players[0].notifications.extend(notifications.into_iter().map(|notification| format!("P{} {}", partner + 1, notification)));
+                }
+                self.hud.update(&mut self.players[0]);
                 
                 // Spawn enemies periodically 
-                self.spawn_enemies();
-                // Update enemies, passing a mutable reference to the player and projectiles, and remove those flagged as destroyed
+                self.spawn_enemies(platform);
+                // Update enemies, passing the players and a mutable reference to the projectiles, and remove those flagged as destroyed
                 self.enemies.retain_mut(|enemy| {
-                    enemy.update(&mut self.player, &mut self.projectiles);
-                    // If the enemy is destroyed, there is a chance to spawn a powerup
-                    if enemy.destroyed && random::u32() % 10 == 0 {
-                        // Spawn power up
-                        self.powerups.push(
-                            Powerup::new_random(
+                    enemy.update(&self.players, &mut self.projectiles, &mut self.rng, platform);
+                    // Loot is rolled for the player who destroyed the enemy, from their score and stats
+                    let killer = enemy.killer;
+                    match &enemy.boss {
+                        // Defeated bosses always drop a heal and their payout, spread out across the ship
+                        Some(boss) if enemy.destroyed => {
+                            for (i, (x, y)) in boss.definition.drops(&enemy.hitbox).into_iter().enumerate() {
+                                let powerup = if i == 0 {
+                                    Powerup::new(x, y, PowerupEffect::Heal, PowerupMovement::FloatVertical(0.5))
+                                } else {
+                                    Powerup::new_random(x, y, &self.players[killer], &mut self.rng)
+                                };
+                                self.players[killer].run_stats.record_drop(&powerup.effect);
+                                self.powerups.push(powerup);
+                            }
+                            self.players[killer].notifications.push(format!("{} DESTROYED! +{}", enemy.enemy_type.to_uppercase(), enemy.points));
+                        }
+                        // If the enemy is destroyed, there is a chance to spawn a powerup
+                        _ if enemy.destroyed && self.rng.loot.u32().is_multiple_of(10) => {
+                            // Spawn power up
+                            let powerup = Powerup::new_random(
                                 enemy.hitbox.x,
                                 enemy.hitbox.y,
-                                &mut self.player,
-                            )
-                        );
+                                &self.players[killer],
+                                &mut self.rng,
+                            );
+                            self.players[killer].run_stats.record_drop(&powerup.effect);
+                            self.powerups.push(powerup);
+                        }
+                        _ => {}
                     }
                     !enemy.destroyed
                 });
                 
+                // Enemies are done moving for this frame, so the grid stays accurate until the player's next update
+                self.grids.enemies.build(self.enemies.iter().map(|enemy| &enemy.hitbox));
+
                 // Update projectiles, remove those flagged as destroyed
+                // Projectiles can request new projectiles or explosions, which are handled once the update is done
+                let mut events = vec![];
                 self.projectiles.retain_mut(|projectile| {
-                    projectile.update(&mut self.player, &mut self.enemies);
+                    events.extend(projectile.update(&mut self.players, &mut self.enemies, &self.grids.enemies, &mut self.rng, platform));
                     !projectile.destroyed
                 });
+                for event in events {
+                    match event {
+                        ProjectileEvent::Spawn(projectile) => self.projectiles.push(projectile),
+                        // Remove opposing projectiles caught in the blast
+                        ProjectileEvent::Blast { x, y, radius, owner } => {
+                            self.projectiles.retain(|projectile| {
+                                projectile.projectile_owner.allied(&owner)
+                                || projectile.beam.is_some()
+                                || !check_circle_collision(x, y, radius, &projectile.hitbox)
+                            });
+                        }
+                    }
+                }
                 
                 // Spawn heal powerups periodically 
-                self.spawn_powerups();
+                self.spawn_powerups(platform);
                 // Update spawned power-ups
                 for powerup in &mut self.powerups {
-                    powerup.update();
+                    powerup.update(platform);
                 }
+                self.grids.powerups.build(self.powerups.iter().map(|powerup| &powerup.hitbox));
 
                 // Increment tick counter for game timing
                 self.tick += 1;
             }
+            // Handled before reading the game's input
+            Scene::Paused | Scene::Options | Scene::Controls => {}
+            // Entering initials, the score is saved once they're confirmed
+            Scene::NameEntry => {
+                if self.name_entry.update(&self.controls[0]) {
+                    let rank = self.save.high_scores.insert(HighScore {
+                        initials: self.name_entry.initials(),
+                        score: self.score(),
+                        level: self.level.level.name.clone(),
+                    });
+                    self.save.save(platform);
+                    self.new_high_score = Some(rank);
+                    self.scenes.transition(TransitionEffect::Wipe, SceneChange::Replace(Scene::HighScores));
+                }
+                self.tick += 1;
+            }
+            // High score table, goes back to the menu or on to a new game after entering a score
+            Scene::HighScores => {
+                if [Button::Start, Button::A, Button::B, Button::X].iter().any(|&button| self.controls[0].just_pressed(button)) {
+                    let change = if self.new_high_score.is_some() { SceneChange::Reset } else { SceneChange::Pop };
+                    self.scenes.transition(TransitionEffect::Wipe, change);
+                }
+                self.tick += 1;
+            }
         }
     }
 
-    fn spawn_powerups(&mut self) {
-        let (screen_w, screen_h) = resolution();
-        // Every 30s, if the player is missing HP, spawn a heal at a random location
-        if self.tick % (60 * 30) == 0 && self.player.hp < self.player.stats.max_hp {
-            self.powerups.push(
-                Powerup::new(
-                    (random::u32() % screen_w) as f32,
-                    24.0 + (random::u32() % screen_h / 2) as f32,
-                    PowerupEffect::Heal,
-                    PowerupMovement::FloatHorizontal(0.75),
-                )
-            );
+    // Whether a joined player pressed fire or pause this frame, through their bindings, to start a run or take over the demo
+    // Read from the controls, since the game's actions come from the autopilot during the demo
+    fn start_pressed(&self) -> bool {
+        (0..self.players.len()).any(|i| {
+            let bindings = self.save.settings.bindings(i);
+            self.controls[i].just_pressed(bindings.button(Action::Fire)) || self.controls[i].just_pressed(bindings.button(Action::Pause))
+        })
+    }
+
+    // Set up a run of the selected level, which begins once the Game scene opens
+    fn start_game(&mut self) {
+        // Start the run from a fresh generator so the recording can reproduce it
+        self.rng = GameRng::new(self.rng.seed);
+        self.difficulty = self.save.settings.difficulty;
+        self.replay = Replay::new(self.rng.seed, self.level_index, self.difficulty);
+        self.level = LevelRunner::new(self.levels[self.level_index].clone());
+        self.players[0].set_max_hp(self.difficulty.player_hp());
+    }
+
+    // Score of the run, the players' scores added together in co-op
+    pub fn score(&self) -> u32 {
+        self.players.iter().map(|player| player.score).sum()
+    }
+
+    // A downed player is revived by a partner flying over their ship, see Player::update_revive
+    fn revive_players(&mut self, platform: &mut dyn Platform) {
+        for i in 0..self.players.len() {
+            if self.players[i].hp > 0 {
+                continue;
+            }
+            let rescued = self
+                .players
+                .iter()
+                .any(|partner| partner.hp > 0 && check_collision(&partner.hitbox, &self.players[i].hitbox));
+            self.players[i].update_revive(rescued, platform);
         }
     }
 
-    fn spawn_enemies(&mut self) {
-        // Start spawning enemies after intro dialog
-        if self.tick > (self.player.notifications.len() as u32 + 1) * 240 {
-            // Enemy spawning logic based on time elapsed
-            // Define spawn intervals (in ticks) for enemies
-            let initial_spawn_rate: u32 = 100; // Initial interval for enemy spawn
-            let minimum_spawn_rate = 25; // Minimum interval after speeding up
-            let speed_up_rate = 60 * 2; // Interval after which spawn rate increases
+    // Nothing moves while paused, not even the tick counter, only the menu reads the controls
+    fn update_pause_menu(&mut self, platform: &dyn Platform) {
+        match self.pause_menu.update(&self.controls[0], platform) {
+            Some(PauseOption::Resume) => {
+                self.scenes.pop();
+            }
+            Some(PauseOption::Restart) => self.scenes.transition(TransitionEffect::Fade, SceneChange::Retry),
+            Some(PauseOption::Quit) => self.scenes.transition(TransitionEffect::Fade, SceneChange::Reset),
+            Some(PauseOption::Options) => {
+                self.options_menu = OptionsMenu::new();
+                self.scenes.push(Scene::Options);
+            }
+            None => {}
+        }
+    }
 
-            // Calculate current spawn interval based on time elapsed
-            let spawn_rate = std::cmp::max(
-                minimum_spawn_rate,
-                initial_spawn_rate.saturating_sub(self.tick / speed_up_rate),
-            );
-            // Spawn a new enemy if the tick is a multiple of the spawn rate
-            if self.tick % spawn_rate == 0 && self.enemies.len() < 24 {
-                // Spawn a random enemy with these probabilities
-                self.enemies.push(match random::u32() % 8 {
-                    0 => Enemy::new(EnemyType::Tank),
-                    1 => Enemy::new(EnemyType::Tank),
-                    2 => Enemy::new(EnemyType::Shooter),
-                    3 => Enemy::new(EnemyType::Shooter),
-                    4 => Enemy::new(EnemyType::Meteor),
-                    5 => Enemy::new(EnemyType::Zipper),
-                    6 => Enemy::new(EnemyType::Turret),
-                    7 => Enemy::new(EnemyType::Turret),
-                    _ => unreachable!(),
-                });
+    // Make a change to the scene stack, once any transition has covered the screen
+    // The tick counter starts over whenever a new scene takes over the screen, overlays leave it alone
+    fn change_scene(&mut self, change: SceneChange, platform: &mut dyn Platform) {
+        match change {
+            SceneChange::Push(scene) => {
+                self.scenes.push(scene);
+                if !scene.is_overlay() {
+                    self.tick = 0;
+                }
+            }
+            SceneChange::Pop => {
+                if self.scenes.pop().is_some_and(|scene| !scene.is_overlay()) {
+                    self.tick = 0;
+                }
+            }
+            SceneChange::Replace(scene) => {
+                self.scenes.replace(scene);
+                self.tick = 0;
+                // A run's input starts with nothing pressed, so it's read the same way when it's played back
+                if scene == Scene::Game {
+                    self.inputs = vec![Actions::default(); self.players.len()];
+                }
+            }
+            SceneChange::Reset => self.restart(platform),
+            SceneChange::Retry => {
+                self.restart(platform);
+                self.start_game();
+                self.change_scene(SceneChange::Replace(Scene::Game), platform);
             }
         }
     }
 
+    // Reset the entire game state to its initial value, holding on to the last run's replay and the selected level
+    // The controls and any running transition are kept too, so they carry on smoothly into the menu
+    fn restart(&mut self, platform: &mut dyn Platform) {
+        let last_replay = self.last_replay.take();
+        let level_index = self.level_index;
+        let controls = std::mem::take(&mut self.controls);
+        let transition = self.scenes.transition.take();
+        *self = Self::with_platform(platform);
+        self.last_replay = last_replay;
+        self.level_index = level_index;
+        self.controls = controls;
+        self.scenes.transition = transition;
+    }
+
+    fn spawn_powerups(&mut self, platform: &dyn Platform) {
+        let (screen_w, screen_h) = platform.resolution();
+        // Every 30s, if a player is missing HP, spawn a heal at a random location
+        let hurt = self.players.iter().position(|player| player.hp < player.stats.max_hp);
+        if let Some(hurt) = hurt.filter(|_| self.tick.is_multiple_of(60 * 30)) {
+            let powerup = Powerup::new(
+                (self.rng.loot.u32() % screen_w) as f32,
+                24.0 + (self.rng.loot.u32() % screen_h / 2) as f32,
+                PowerupEffect::Heal,
+                PowerupMovement::FloatHorizontal(0.75),
+            );
+            // counted for the first player it was spawned for
+            self.players[hurt].run_stats.record_drop(&powerup.effect);
+            self.powerups.push(powerup);
+        }
+    }
+
+    // Run the level script, which spawns enemies
+    fn spawn_enemies(&mut self, platform: &dyn Platform) {
+        // Hold the script while a boss makes its entrance
+        if self.enemies.iter().any(|enemy| enemy.boss.as_ref().is_some_and(|boss| boss.entering)) {
+            return;
+        }
+        let finished = self.level.finished();
+        let count = self.enemies.len();
+        self.level.update(&mut self.enemies, &self.enemy_definitions, &mut self.rng, platform);
+        for enemy in &mut self.enemies[count..] {
+            enemy.set_max_hp(self.difficulty.enemy_hp(enemy.hp));
+        }
+        // Warn the players about bosses that just spawned, the first player's notifications are shown to everyone
+        for enemy in self.enemies[count..].iter().filter(|enemy| enemy.boss.is_some()) {
+            self.players[0].notifications.push(format!("WARNING! {} APPROACHING", enemy.enemy_type.to_uppercase()));
+        }
+        if !finished && self.level.finished() {
+            self.players[0].notifications.push(format!("{} CLEAR!", self.level.level.name));
+        }
+    }
+
     // Renders all game elements each frame
     // Rendering is sequential from background to foreground
     fn draw_game_elements(self: &GameState) {
         let (screen_w, screen_h) = resolution();
         // Draw moving parallax stars in the background
         self.draw_stars(screen_w, screen_h);
-        // Draw enemies
-        for enemy in &self.enemies {
-            enemy.draw();
-        }
-        // Draw powerups
-        for powerup in &self.powerups {
-            powerup.draw(self.tick);
+        let visible = self.scenes.visible();
+        // The run's entities are only drawn while its Game or GameOver scene is showing
+        let in_run = visible.iter().any(|scene| matches!(scene, Scene::Game | Scene::GameOver));
+        let flash_color = self.save.settings.hit_flash_color();
+        if in_run {
+            // Draw enemies
+            for enemy in &self.enemies {
+                enemy.draw(flash_color);
+            }
+            // Draw powerups
+            for powerup in &self.powerups {
+                powerup.draw(self.tick);
+            }
+            // Drawing the players
+            if visible.contains(&Scene::Game) {
+                for player in &self.players {
+                    player.draw(flash_color);
+                }
+            }
+            // Draw projectiles
+            for projectile in &self.projectiles {
+                projectile.draw();
+            }
         }
-        // Drawing the player
-        if self.scene == Scene::Game {
-            self.player.draw();
-        } else if self.scene == Scene::GameOver {
-            self.hud.draw_game_over(self.tick);
+        // Draw game HUD
+        self.hud.draw(&self.players, self.save.high_scores.best(), &self.save.settings.bindings(0));
+        if in_run {
+            for enemy in self.enemies.iter().filter(|enemy| enemy.boss.is_some()) {
+                self.hud.draw_boss(enemy);
+            }
+            if self.playback.is_some() {
+                self.hud.draw_replay(self.tick);
+            }
+            if self.autopilot.is_some() {
+                self.hud.draw_demo(self.tick);
+            }
         }
-        // Draw projectiles
-        for projectile in &self.projectiles {
-            projectile.draw();
+        // Draw each visible scene's own screen, from the bottom up so overlays end up on top
+        for scene in visible {
+            match scene {
+                Scene::Menu => self.hud.draw_menu(self.tick, self.last_replay.is_some(), &self.levels[self.level_index].name),
+                Scene::Game => self.hud.draw_notifications(&self.players[0]),
+                Scene::GameOver => self.hud.draw_game_over(self.tick),
+                Scene::Paused => self.hud.draw_pause_menu(&self.pause_menu),
+                Scene::NameEntry => self.hud.draw_name_entry(&self.name_entry, self.score(), self.tick),
+                Scene::HighScores => self.hud.draw_high_scores(&self.save.high_scores, self.new_high_score, self.tick),
+                Scene::Options => self.hud.draw_options(&self.options_menu, &self.save.settings),
+                Scene::Controls => self.hud.draw_controls(&self.controls_menu, &self.save.settings.bindings(self.controls_menu.gamepad)),
+            }
         }
-        // Draw game HUD
-        self.hud.draw(&self.player);
-        if self.scene == Scene::Game {
-            self.hud.draw_notifications(&self.player);
+        // Transitions cover everything
+        if let Some(transition) = &self.scenes.transition {
+            self.hud.draw_transition(transition);
         }
     }
 
@@ -203,26 +636,259 @@ impl GameState {
             (67890, 2, 0.35, 10),
         ];
 
+        // Faint nebula clouds drifting behind the stars
+        for row in 0..=screen_h / 32 {
+            for col in 0..=screen_w / 32 {
+                let cloud = value_noise_2d(
+                    col as f32 * 0.35,
+                    (row as f32 - self.tick as f32 * 0.01) * 0.35,
+                    24680,
+                );
+                // only the densest parts of the noise become visible
+                if cloud > 0.6 {
+                    let alpha = ((cloud - 0.6) * 40.0) as u32;
+                    circ!(
+                        x = col as i32 * 32 - 8,
+                        y = row as i32 * 32 - 8,
+                        d = 48,
+                        color = 0x6633aa00 | alpha
+                    );
+                }
+            }
+        }
+
         for &(seed, size, speed, count) in star_layers.iter() {
             for i in 0..count {
-                let rand_x = rand_with_seed(seed + i + self.tick / 10) % screen_w;
-                let rand_y = (rand_with_seed(seed + i + self.tick / 10) / screen_w) % screen_h;
+                let hash = hash_2d(seed, i + self.tick / 10);
+                let rand_x = hash % screen_w;
+                let rand_y = (hash / screen_w) % screen_h;
 
                 // Adjust position slightly based on player movement
-                let adjust_x = self.player.hitbox.x * speed / 5.0;
-                let adjust_y = self.player.hitbox.y * speed / 5.0;
+                let adjust_x = self.players[0].hitbox.x * speed / 5.0;
+                let adjust_y = self.players[0].hitbox.y * speed / 5.0;
 
                 let x = rand_x as i32 - adjust_x as i32;
                 let y = (self.tick as f32 * speed) as i32 + rand_y as i32 + adjust_y as i32;
 
-                // Draw the star
+                // Twinkle by smoothly varying each star's brightness
+                let twinkle = value_noise_1d(self.tick as f32 / 20.0, hash_2d(seed, i));
+                let alpha = 0x22 + (twinkle * 0x44 as f32) as u32;
+
+                // Draw the star, wrapping around the screen edges
                 circ!(
-                    x = x % screen_w as i32,
-                    y = y % screen_h as i32,
+                    x = x.rem_euclid(screen_w as i32),
+                    y = y.rem_euclid(screen_h as i32),
                     d = size,
-                    color = 0xFFFFFF44
+                    color = 0xFFFFFF00 | alpha
                 );
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Input holding the given buttons for a number of frames
+    fn hold(buttons: &[Button], frames: usize) -> Vec<InputFrame> {
+        let mut frame = InputFrame::default();
+        for &button in buttons {
+            frame.set(button, true);
+        }
+        vec![frame; frames]
+    }
+
+    // Starts a run from the menu with A, then plays the rest of the script
+    fn play(seed: u32, script: Vec<InputFrame>) -> (GameState, MockPlatform) {
+        let mut input = hold(&[Button::A], 1);
+        input.extend(hold(&[], 59)); // the fade into the run
+        input.extend(script);
+        let frames = input.len();
+        let mut platform = MockPlatform::new(seed, input);
+        let mut state = GameState::with_platform(&mut platform);
+        for _ in 0..frames {
+            state.step(&mut platform);
+        }
+        (state, platform)
+    }
+
+    #[test]
+    fn scripted_input_moves_and_fires() {
+        let (still, _) = play(1, hold(&[], 30));
+        assert_eq!(still.scenes.top(), Scene::Game);
+        let (moved, platform) = play(1, hold(&[Button::Right, Button::A], 30));
+        assert!(moved.players[0].hitbox.x > still.players[0].hitbox.x);
+        assert_eq!(moved.players[0].hitbox.y, still.players[0].hitbox.y);
+        assert!(platform.sounds.iter().any(|sound| sound == "projectile_player_shoot"));
+    }
+
+    #[test]
+    fn getting_hit_shakes_the_camera() {
+        let (mut state, mut platform) = play(1, vec![]);
+        assert_eq!(platform.shake, None);
+        let (x, y) = (state.players[0].hitbox.x, state.players[0].hitbox.y);
+        let projectile = Projectile::new(x, y, 0.0, 90.0, ProjectileType::Basic, ProjectileOwner::Enemy, &mut state.rng, &mut platform);
+        state.projectiles.push(projectile);
+        let hp = state.players[0].hp;
+        state.step(&mut platform);
+        assert!(state.players[0].hp < hp);
+        assert!(platform.shake.is_some());
+        assert!(platform.sounds.iter().any(|sound| sound == "projectile_enemy_shoot"));
+    }
+
+    #[test]
+    fn bomb_button_fires_the_bomb_without_switching() {
+        let (mut state, mut platform) = play(1, vec![]);
+        state.players[0].weapons.push(Weapon::new(ProjectileType::Bomb));
+        platform.script.extend(hold(&[Button::Select], 1));
+        state.step(&mut platform);
+        assert!(state.projectiles.iter().any(|projectile| projectile.projectile_type == ProjectileType::Bomb));
+        assert_eq!(state.players[0].weapon_index, 0);
+        assert_eq!(state.players[0].weapons[1].ammo, Some(7));
+    }
+
+    #[test]
+    fn bombs_keep_their_own_cooldown() {
+        let (mut state, mut platform) = play(1, vec![]);
+        state.players[0].weapons.push(Weapon::new(ProjectileType::Bomb));
+        platform.script.extend(hold(&[Button::Select], 1));
+        platform.script.extend(hold(&[Button::A], 1));
+        platform.script.extend(hold(&[Button::Select], 1));
+        let count = |state: &GameState, projectile_type: ProjectileType| {
+            state.projectiles.iter().filter(|projectile| projectile.projectile_type == projectile_type).count()
+        };
+        state.step(&mut platform);
+        assert_eq!(count(&state, ProjectileType::Bomb), 1);
+        // the blaster fires on the very next frame
+        state.step(&mut platform);
+        assert_eq!(count(&state, ProjectileType::Basic), 2);
+        // but another bomb has to wait
+        state.step(&mut platform);
+        assert_eq!(count(&state, ProjectileType::Bomb), 1);
+        assert_eq!(state.players[0].weapons[1].ammo, Some(7));
+    }
+
+    #[test]
+    fn weapon_hints_name_the_bound_buttons() {
+        let platform = MockPlatform::new(1, vec![]);
+        let mut player = Player::new(0, &platform);
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::NextWeapon, Button::Y);
+        for projectile_type in [ProjectileType::Splatter, ProjectileType::Bomb] {
+            let powerup = Powerup::new(0.0, 0.0, PowerupEffect::Weapon(projectile_type), PowerupMovement::Static);
+            player.collect_powerup(&powerup, &bindings);
+        }
+        assert!(player.notifications.contains(&"GOT SPLATTER! Y/X TO SWITCH".to_string()));
+        assert!(player.notifications.contains(&"GOT BOMB! SELECT TO DROP".to_string()));
+    }
+
+    #[test]
+    fn size_boosts_grow_fired_projectiles() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut rng = GameRng::new(1);
+        let mut player = Player::new(0, &platform);
+        let powerup = Powerup::new(0.0, 0.0, PowerupEffect::ProjectileSizeBoost, PowerupMovement::Static);
+        for _ in 0..4 {
+            player.collect_powerup(&powerup, &Bindings::default());
+        }
+        // stats pick up the boost once the player next recomputes them, base stats are up to date straight away
+        assert_eq!(player.base_stats.projectile_size, 12);
+        let mut projectiles = vec![];
+        for projectile_type in [ProjectileType::Basic, ProjectileType::Bomb] {
+            let mut weapon = Weapon::new(projectile_type);
+            weapon.fire(0, &player.hitbox, &player.base_stats, &mut projectiles, &mut rng, &mut platform);
+        }
+        let sizes: Vec<u32> = projectiles.iter().map(|projectile| projectile.hitbox.w).collect();
+        assert_eq!(sizes[..2], [12, 12]);
+        assert!(sizes[2] > Projectile::new(0.0, 0.0, 1.0, -90.0, ProjectileType::Bomb, ProjectileOwner::Player(0), &mut rng, &mut platform).hitbox.w);
+    }
+
+    #[test]
+    fn both_players_pick_up_powerups_on_the_same_frame() {
+        let (mut state, mut platform) = play(1, vec![]);
+        let mut partner = Player::new(1, &platform);
+        partner.hitbox.x += 64.0;
+        state.players.push(partner);
+        state.inputs.push(Actions::default());
+        // the first player's pickup comes first, so the partner's would shift down an index once it's gone
+        for player in &state.players {
+            let (x, y) = (player.hitbox.x + 4.0, player.hitbox.y + 4.0);
+            state.powerups.push(Powerup::new(x, y, PowerupEffect::RateOfFireBoost, PowerupMovement::Static));
+        }
+        state.grids.powerups.build(state.powerups.iter().map(|powerup| &powerup.hitbox));
+        state.step(&mut platform);
+        assert!(state.powerups.is_empty());
+        // the partner's notifications are shown with the first player's
+        let notifications = &state.players[0].notifications;
+        assert!(notifications.contains(&"+1 RATE OF FIRE".to_string()));
+        assert!(notifications.contains(&"P2 +1 RATE OF FIRE".to_string()));
+    }
+
+    #[test]
+    fn drops_are_for_the_player_who_made_the_kill() {
+        let (mut state, mut platform) = play(1, vec![]);
+        state.players.push(Player::new(1, &platform));
+        state.inputs.push(Actions::default());
+        let definition = state.enemy_definitions.iter().find(|definition| definition.boss.is_some()).unwrap();
+        let mut boss = Enemy::new(definition, &mut state.rng, &platform);
+        boss.boss.as_mut().unwrap().entering = false;
+        let hp = boss.hp;
+        boss.take_damage(&mut state.players[1], hp);
+        let payout = boss.boss.as_ref().unwrap().definition.payout;
+        state.enemies.push(boss);
+        state.step(&mut platform);
+        let drops = |player: &Player| player.run_stats.drops.iter().map(|(_, count)| count).sum::<u32>();
+        assert_eq!(drops(&state.players[1]), payout + 1);
+        assert_eq!(drops(&state.players[0]), 0);
+        state.step(&mut platform);
+        assert!(state.players[0].notifications.iter().any(|notification| notification.starts_with("P2 DREADNOUGHT DESTROYED!")));
+    }
+
+    #[test]
+    fn runs_start_on_the_rebound_fire_button() {
+        let mut platform = MockPlatform::new(1, [hold(&[Button::A], 1), hold(&[], 1), hold(&[Button::X], 1)].concat());
+        let mut state = GameState::with_platform(&mut platform);
+        state.save.settings.bindings_mut(0).bind(Action::Fire, Button::X);
+        state.step(&mut platform);
+        state.step(&mut platform);
+        assert!(state.scenes.transition.is_none());
+        state.step(&mut platform);
+        assert!(state.scenes.transition.is_some());
+    }
+
+    #[test]
+    fn same_seed_and_input_play_the_same() {
+        let mut script = hold(&[Button::Left, Button::A], 200);
+        script.extend(hold(&[Button::Up, Button::B], 100));
+        script.extend(hold(&[Button::Right, Button::A], 300));
+        let (first, first_platform) = play(42, script.clone());
+        let (second, second_platform) = play(42, script);
+        assert_eq!(serde_json::to_string(&first).unwrap(), serde_json::to_string(&second).unwrap());
+        assert_eq!(first_platform.sounds, second_platform.sounds);
+        assert_eq!(first_platform.shake, second_platform.shake);
+    }
+
+    #[test]
+    fn recorded_runs_play_back_the_same() {
+        let mut script = hold(&[Button::Left, Button::A], 400);
+        script.extend(hold(&[Button::Right, Button::A], 400));
+        let (run, _) = play(42, script);
+        // long enough for enemies to show up and shoot back
+        assert!(!run.enemies.is_empty() && run.players[0].hp < 3);
+        // watched from the menu of a game with a different seed, with nothing pressed while it plays
+        let mut input = hold(&[Button::Select], 1);
+        input.extend(hold(&[], 59 + 800));
+        let frames = input.len();
+        let mut platform = MockPlatform::new(7, input);
+        let mut watched = GameState::with_platform(&mut platform);
+        watched.last_replay = Some(run.replay.clone());
+        for _ in 0..frames {
+            watched.step(&mut platform);
+        }
+        assert!(watched.playback.is_some());
+        assert_eq!(serde_json::to_string(&watched.players).unwrap(), serde_json::to_string(&run.players).unwrap());
+        assert_eq!(serde_json::to_string(&watched.enemies).unwrap(), serde_json::to_string(&run.enemies).unwrap());
+        assert_eq!(serde_json::to_string(&watched.projectiles).unwrap(), serde_json::to_string(&run.projectiles).unwrap());
+    }
+}
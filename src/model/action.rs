@@ -0,0 +1,168 @@
+use super::*;
+
+// Things the player can do during a run
+// Gameplay reads these instead of gamepad buttons, and each one can be bound to any button
+#[turbo::serialize]
+#[derive(PartialEq, Copy)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    NextWeapon,
+    PreviousWeapon,
+    Focus, // move at half speed, for squeezing between bullets
+    Bomb, // fire a bomb straight away, if there are any, without switching to them
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::NextWeapon,
+        Action::PreviousWeapon,
+        Action::Focus,
+        Action::Bomb,
+        Action::Pause,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "MOVE UP",
+            Action::MoveDown => "MOVE DOWN",
+            Action::MoveLeft => "MOVE LEFT",
+            Action::MoveRight => "MOVE RIGHT",
+            Action::Fire => "FIRE",
+            Action::NextWeapon => "NEXT WEAPON",
+            Action::PreviousWeapon => "PREV WEAPON",
+            Action::Focus => "FOCUS",
+            Action::Bomb => "BOMB",
+            Action::Pause => "PAUSE",
+        }
+    }
+
+    // Button the action is on until it's rebound
+    pub fn default_button(&self) -> Button {
+        match self {
+            Action::MoveUp => Button::Up,
+            Action::MoveDown => Button::Down,
+            Action::MoveLeft => Button::Left,
+            Action::MoveRight => Button::Right,
+            Action::Fire => Button::A,
+            Action::NextWeapon => Button::B,
+            Action::PreviousWeapon => Button::X,
+            Action::Focus => Button::Y,
+            Action::Bomb => Button::Select,
+            Action::Pause => Button::Start,
+        }
+    }
+}
+
+// Actions held on a single frame, along with the pointer
+// Runs are recorded as these, so a replay plays back the same however the controls are bound
+#[turbo::serialize]
+#[derive(PartialEq, Default)]
+pub struct ActionFrame {
+    actions: u16, // one bit per Action
+    pub pointer: Option<(i32, i32)>, // screen position of the pointer while it's pressed
+}
+
+impl ActionFrame {
+    pub fn set(&mut self, action: Action, active: bool) {
+        if active {
+            self.actions |= 1 << action as u16;
+        } else {
+            self.actions &= !(1 << action as u16);
+        }
+    }
+
+    pub fn active(&self, action: Action) -> bool {
+        self.actions & (1 << action as u16) != 0
+    }
+}
+
+// Actions for the current frame along with the previous one, used to tell when an action was just started
+#[turbo::serialize]
+#[derive(Default)]
+pub struct Actions {
+    pub frame: ActionFrame,
+    previous: ActionFrame,
+}
+
+impl Actions {
+    // Moves on to the next frame of actions
+    pub fn update(&mut self, frame: ActionFrame) {
+        self.previous = std::mem::replace(&mut self.frame, frame);
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.frame.active(action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.frame.active(action) && !self.previous.active(action)
+    }
+
+    // Screen position of the pointer while it's pressed
+    pub fn pointer(&self) -> Option<(i32, i32)> {
+        self.frame.pointer
+    }
+}
+
+// Which button each action is on, for one gamepad
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub struct Bindings {
+    buttons: Vec<(Action, Button)>, // actions missing from the list, e.g. in older saves, are on their default button
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            buttons: Action::ALL.iter().map(|&action| (action, action.default_button())).collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn button(&self, action: Action) -> Button {
+        self.buttons
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(action.default_button(), |&(_, button)| button)
+    }
+
+    // Puts an action on a button
+    // Whatever action was on that button takes the action's old one, so nothing is ever left out of reach
+    pub fn bind(&mut self, action: Action, button: Button) {
+        let old = self.button(action);
+        self.buttons = Action::ALL
+            .iter()
+            .map(|&other| {
+                let bound = self.button(other);
+                match other {
+                    _ if other == action => (other, button),
+                    _ if bound == button => (other, old),
+                    _ => (other, bound),
+                }
+            })
+            .collect();
+    }
+
+    // Translates the gamepad's buttons into actions, the pointer is passed through as is
+    pub fn map(&self, frame: &InputFrame) -> ActionFrame {
+        let mut actions = ActionFrame {
+            actions: 0,
+            pointer: frame.pointer,
+        };
+        for action in Action::ALL {
+            actions.set(action, frame.pressed(self.button(action)));
+        }
+        actions
+    }
+}
@@ -0,0 +1,147 @@
+use super::*;
+
+// Bot that plays the game, producing the same input a player would
+// Drives the attract mode demo on the menu and the balance simulator
+#[turbo::serialize]
+pub struct Autopilot {
+    last: InputFrame, // input from the previous frame, so buttons can be tapped instead of held
+}
+
+// How far ahead the autopilot predicts movement, in frames
+const LOOKAHEAD: u32 = 24;
+// Extra space the autopilot tries to keep around threats, in pixels
+const MARGIN: f32 = 4.0;
+
+impl Autopilot {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Autopilot {
+            last: InputFrame::default(),
+        }
+    }
+
+    // Picks this frame's input by trying every direction the player can move in
+    // and choosing the one that stays clear of predicted threats while heading for the current goal
+    pub fn input(&mut self, player: &Player, enemies: &[Enemy], projectiles: &[Projectile], powerups: &[Powerup], platform: &dyn Platform) -> InputFrame {
+        let screen = platform.resolution();
+        let mut frame = InputFrame::default();
+        // Always shooting, which also starts the game from the menu
+        frame.set(Button::A, true);
+
+        let (goal_x, goal_y) = self.goal(player, enemies, powerups, screen);
+        let mut best = (0.0, 0.0);
+        let mut best_score = f32::MAX;
+        for dy in [-1.0, 0.0, 1.0] {
+            for dx in [-1.0, 0.0, 1.0] {
+                let danger = self.danger(player, enemies, projectiles, (dx, dy), screen);
+                // distance to the goal after a few frames of moving in this direction
+                let (x, y) = predict_player(player, (dx, dy), 8, screen);
+                let distance = ((x - goal_x).powi(2) + (y - goal_y).powi(2)).sqrt();
+                let score = danger * 1000.0 + distance;
+                if score < best_score {
+                    best_score = score;
+                    best = (dx, dy);
+                }
+            }
+        }
+        frame.set(Button::Left, best.0 < 0.0);
+        frame.set(Button::Right, best.0 > 0.0);
+        frame.set(Button::Up, best.1 < 0.0);
+        frame.set(Button::Down, best.1 > 0.0);
+
+        // Switch away from the blaster whenever another weapon is available,
+        // tapping the button since switching happens when it's just pressed
+        if player.weapon_index == 0 && player.weapons.len() > 1 && !self.last.pressed(Button::B) {
+            frame.set(Button::B, true);
+        }
+
+        self.last = frame.clone();
+        frame
+    }
+
+    // Where the autopilot wants the center of the ship to be
+    // Powerups come first, then lining up with the lowest enemy, otherwise waiting near the bottom of the screen
+    fn goal(&self, player: &Player, enemies: &[Enemy], powerups: &[Powerup], screen: (u32, u32)) -> (f32, f32) {
+        let (screen_w, screen_h) = screen;
+        let (px, py) = center(&player.hitbox);
+        let home_y = screen_h as f32 - 96.0;
+        let powerup = powerups
+            .iter()
+            .map(|powerup| center(&powerup.hitbox))
+            .filter(|&(_, y)| y > 24.0)
+            .min_by(|a, b| {
+                let da = (a.0 - px).powi(2) + (a.1 - py).powi(2);
+                let db = (b.0 - px).powi(2) + (b.1 - py).powi(2);
+                da.total_cmp(&db)
+            });
+        if let Some(goal) = powerup {
+            return goal;
+        }
+        let target = enemies
+            .iter()
+            .filter(|enemy| !enemy.destroyed && enemy.hitbox.y > 0.0)
+            .max_by(|a, b| a.hitbox.y.total_cmp(&b.hitbox.y));
+        match target {
+            Some(enemy) => (center(&enemy.hitbox).0, home_y),
+            None => (screen_w as f32 / 2.0, home_y),
+        }
+    }
+
+    // How dangerous it is to keep moving in the given direction, higher for threats that would hit sooner
+    fn danger(&self, player: &Player, enemies: &[Enemy], projectiles: &[Projectile], direction: (f32, f32), screen: (u32, u32)) -> f32 {
+        let mut danger = 0.0;
+        for t in (0..=LOOKAHEAD).step_by(2) {
+            let (x, y) = predict_player(player, direction, t, screen);
+            let ship = Hitbox {
+                x: x - player.hitbox.w as f32 / 2.0 - MARGIN,
+                y: y - player.hitbox.h as f32 / 2.0 - MARGIN,
+                w: player.hitbox.w + MARGIN as u32 * 2,
+                h: player.hitbox.h + MARGIN as u32 * 2,
+            };
+            // threats that would hit sooner weigh more
+            let weight = (LOOKAHEAD + 1 - t) as f32;
+            for projectile in projectiles {
+                if projectile.projectile_owner != ProjectileOwner::Enemy {
+                    continue;
+                }
+                let mut hitbox = projectile.hitbox.clone();
+                // beams and puddles stay where they are, anything still flying follows its heading
+                if projectile.beam.is_none() && !projectile.collided {
+                    let radian_angle = projectile.angle.to_radians();
+                    hitbox.x += projectile.velocity * radian_angle.cos() * t as f32;
+                    hitbox.y += projectile.velocity * radian_angle.sin() * t as f32;
+                } else if projectile.collided && projectile.projectile_type != ProjectileType::Splatter {
+                    continue;
+                }
+                if check_collision(&ship, &hitbox) {
+                    danger += weight;
+                }
+            }
+            for enemy in enemies.iter().filter(|enemy| !enemy.destroyed) {
+                let mut hitbox = enemy.hitbox.clone();
+                hitbox.y += enemy.speed * t as f32;
+                if check_collision(&ship, &hitbox) {
+                    danger += weight;
+                }
+            }
+        }
+        danger
+    }
+}
+
+// Center of the player's ship after moving in the given direction for t frames, matching Player::update
+fn predict_player(player: &Player, direction: (f32, f32), t: u32, screen: (u32, u32)) -> (f32, f32) {
+    let ((dx, dy), (screen_w, screen_h)) = (direction, screen);
+    let len = (dx * dx + dy * dy).sqrt();
+    let (mut x, mut y) = (player.hitbox.x, player.hitbox.y);
+    if len > 0.0 {
+        let distance = player.stats.speed * t as f32;
+        x = (x + dx / len * distance).clamp(0.0, (screen_w - player.hitbox.w) as f32);
+        y = (y + dy / len * distance).clamp(0.0, (screen_h - player.hitbox.h) as f32);
+    }
+    (x + player.hitbox.w as f32 / 2.0, y + player.hitbox.h as f32 / 2.0)
+}
+
+fn center(hitbox: &Hitbox) -> (f32, f32) {
+    (hitbox.x + hitbox.w as f32 / 2.0, hitbox.y + hitbox.h as f32 / 2.0)
+}
@@ -0,0 +1,186 @@
+use super::*;
+
+// How an enemy fights as a boss, as defined in data/enemies.json
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub struct BossDefinition {
+    pub entrance_y: f32, // y position the boss flies in to before the fight starts
+    pub payout: u32, // number of powerups dropped when the boss is destroyed, on top of a heal
+    pub phases: Vec<BossPhase>,
+}
+
+// A stage of the fight, starting once the boss is down to the given share of its hp
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub struct BossPhase {
+    pub hp: u32, // percentage of max hp at which the phase starts, the first phase starts at 100
+    pub patterns: Vec<BulletPattern>, // fired together from the center of the boss
+    pub interval: u32, // frames between volleys
+    pub speed: f32, // horizontal speed while swaying from side to side
+}
+
+// Fight state of an enemy that is a boss
+#[turbo::serialize]
+pub struct Boss {
+    pub definition: BossDefinition,
+    pub max_hp: u32,
+    pub phase: usize, // index of the current phase
+    pub entering: bool, // flying in, invulnerable and not attacking yet
+    timer: u32, // frames spent in the current phase
+    volleys: u32, // volleys fired in the current phase
+    direction: f32, // 1.0 while swaying right, -1.0 while swaying left
+}
+
+impl Boss {
+    pub fn new(definition: &BossDefinition, max_hp: u32) -> Self {
+        Boss {
+            definition: definition.clone(),
+            max_hp,
+            phase: 0,
+            entering: true,
+            timer: 0,
+            volleys: 0,
+            direction: 1.0,
+        }
+    }
+
+    // The phase the fight is in
+    pub fn phase(&self) -> &BossPhase {
+        &self.definition.phases[self.phase]
+    }
+
+    // Moves the boss and returns the number of the volley to fire this frame, if any
+    // Bosses fly in from the top of the screen, then sway from side to side,
+    // switching to the next phase whenever their hp drops below its threshold
+    pub fn update(&mut self, enemy: &mut Hitbox, hp: u32, speed: f32, platform: &mut dyn Platform) -> Option<u32> {
+        let (screen_w, _) = platform.resolution();
+
+        if self.entering {
+            enemy.y += speed;
+            if enemy.y >= self.definition.entrance_y {
+                enemy.y = self.definition.entrance_y;
+                self.entering = false;
+                self.timer = 0;
+            }
+            return None;
+        }
+
+        // Move on to the next phase once hp drops low enough
+        if let Some(next) = self.definition.phases.get(self.phase + 1) {
+            if hp * 100 <= next.hp * self.max_hp {
+                self.phase += 1;
+                self.timer = 0;
+                self.volleys = 0;
+                platform.play_sound("projectile_hit");
+            }
+        }
+        let phase = &self.definition.phases[self.phase];
+
+        // Sway from side to side, turning around at the edges of the screen
+        enemy.x += phase.speed * self.direction;
+        if enemy.x < 0.0 || enemy.x + enemy.w as f32 > screen_w as f32 {
+            enemy.x = enemy.x.clamp(0.0, (screen_w - enemy.w) as f32);
+            self.direction = -self.direction;
+        }
+
+        // Fire a volley every interval frames
+        let mut volley = None;
+        if self.timer.is_multiple_of(phase.interval) {
+            volley = Some(self.volleys);
+            self.volleys += 1;
+        }
+        self.timer += 1;
+        volley
+    }
+}
+
+impl BossDefinition {
+    // Whether the phases can be played through: starting at full hp, with thresholds going down
+    // and every phase firing at least one valid pattern
+    pub fn is_valid(&self) -> bool {
+        let Some(first) = self.phases.first() else {
+            return false;
+        };
+        first.hp == 100
+            && self.phases.windows(2).all(|pair| pair[1].hp < pair[0].hp)
+            && self.phases.iter().all(|phase| {
+                phase.interval > 0
+                    && !phase.patterns.is_empty()
+                    && phase.patterns.iter().all(BulletPattern::is_valid)
+                    && phase.speed.is_finite()
+                    && phase.speed >= 0.0
+            })
+    }
+
+    // Where a defeated boss drops its powerups, a heal then the payout, spread out across the ship
+    pub fn drops(&self, hitbox: &Hitbox) -> Vec<(f32, f32)> {
+        let count = self.payout + 1;
+        (0..count)
+            .map(|i| {
+                let x = hitbox.x + hitbox.w as f32 * (i + 1) as f32 / (count + 1) as f32;
+                let y = hitbox.y + hitbox.h as f32 / 2.0;
+                (x, y)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dreadnought() -> EnemyDefinition {
+        EnemyDefinition::load().unwrap().into_iter().find(|definition| definition.boss.is_some()).unwrap()
+    }
+
+    #[test]
+    fn phases_switch_at_their_share_of_max_hp() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let definition = dreadnought().boss.unwrap();
+        let thresholds: Vec<u32> = definition.phases.iter().map(|phase| phase.hp).collect();
+        assert_eq!(thresholds, [100, 60, 30]);
+        let mut boss = Boss::new(&definition, 200);
+        boss.entering = false;
+        let mut hitbox = Hitbox { x: 0.0, y: 0.0, w: 64, h: 48 };
+        // 121 of 200 is still above 60%, 120 is exactly on it
+        boss.update(&mut hitbox, 121, 0.5, &mut platform);
+        assert_eq!(boss.phase, 0);
+        boss.update(&mut hitbox, 120, 0.5, &mut platform);
+        assert_eq!(boss.phase, 1);
+        assert_eq!(platform.sounds, ["projectile_hit"]);
+        // one phase at a time, even when hp drops past several thresholds at once
+        boss.update(&mut hitbox, 1, 0.5, &mut platform);
+        assert_eq!(boss.phase, 2);
+        boss.update(&mut hitbox, 0, 0.5, &mut platform);
+        assert_eq!(boss.phase, 2);
+    }
+
+    #[test]
+    fn bosses_cant_be_hurt_while_flying_in() {
+        let platform = MockPlatform::new(1, vec![]);
+        let mut player = Player::new(0, &platform);
+        let definition = dreadnought();
+        let mut enemy = Enemy::new(&definition, &mut GameRng::new(1), &platform);
+        enemy.take_damage(&mut player, 5);
+        assert_eq!(enemy.hp, definition.hp);
+        let mut platform = MockPlatform::new(1, vec![]);
+        let boss = enemy.boss.as_mut().unwrap();
+        while boss.entering {
+            assert_eq!(boss.update(&mut enemy.hitbox, enemy.hp, enemy.speed, &mut platform), None);
+        }
+        assert_eq!(enemy.hitbox.y, boss.definition.entrance_y);
+        enemy.take_damage(&mut player, 5);
+        assert_eq!(enemy.hp, definition.hp - 5);
+    }
+
+    #[test]
+    fn defeated_bosses_drop_a_heal_and_their_payout() {
+        let definition = dreadnought().boss.unwrap();
+        let hitbox = Hitbox { x: 10.0, y: 20.0, w: 64, h: 48 };
+        let drops = definition.drops(&hitbox);
+        assert_eq!(drops.len(), definition.payout as usize + 1);
+        // spread across the ship, never past its edges
+        assert!(drops.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(drops.iter().all(|&(x, y)| x > 10.0 && x < 74.0 && y == 44.0));
+    }
+}
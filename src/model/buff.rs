@@ -0,0 +1,121 @@
+use super::*;
+
+// Different kinds of temporary buffs granted by powerups
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub enum BuffKind {
+    Speed,  // 1.1x speed per stack
+    Damage, // +1 damage per stack
+}
+
+// Struct for a buff currently active on the player
+#[turbo::serialize]
+pub struct Buff {
+    pub kind: BuffKind,
+    pub remaining: u32, // frames left before the buff expires
+    pub stacks: u32,
+}
+
+impl BuffKind {
+    // How long a buff lasts, refreshed whenever another stack is picked up
+    pub fn duration(&self) -> u32 {
+        match self {
+            BuffKind::Speed => 60 * 10,
+            BuffKind::Damage => 60 * 8,
+        }
+    }
+
+    // Maximum number of times a buff can stack
+    pub fn max_stacks(&self) -> u32 {
+        match self {
+            BuffKind::Speed => 3,
+            BuffKind::Damage => 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuffKind::Speed => "SPEED BOOST",
+            BuffKind::Damage => "DAMAGE BOOST",
+        }
+    }
+
+    // Sprite used for the buff's icon in the HUD
+    pub fn sprite(&self) -> &'static str {
+        match self {
+            BuffKind::Speed => "power_ups/powerup_speed",
+            BuffKind::Damage => "power_ups/powerup_damage",
+        }
+    }
+}
+
+impl Buff {
+    pub fn new(kind: BuffKind) -> Self {
+        Buff {
+            remaining: kind.duration(),
+            stacks: 1,
+            kind,
+        }
+    }
+
+    // Applies the buff's modifiers on top of the given stats
+    pub fn apply(&self, stats: &mut PlayerStats) {
+        match self.kind {
+            BuffKind::Speed => stats.speed *= 1.1_f32.powi(self.stacks as i32),
+            BuffKind::Damage => stats.damage += self.stacks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs frames of a player that isn't pressing anything, with nothing else in the game
+    fn idle(player: &mut Player, frames: u32, platform: &mut MockPlatform) {
+        let grids = Grids::new(platform);
+        for _ in 0..frames {
+            player.update(&Actions::default(), &Bindings::default(), &mut vec![], &mut vec![], &mut [], &grids, &mut GameRng::new(1), platform);
+        }
+    }
+
+    #[test]
+    fn stacks_are_capped_and_refresh_the_duration() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut player = Player::new(0, &platform);
+        player.add_buff(BuffKind::Damage);
+        idle(&mut player, 100, &mut platform);
+        assert_eq!(player.buffs[0].remaining, BuffKind::Damage.duration() - 100);
+        for _ in 0..3 {
+            player.add_buff(BuffKind::Damage);
+        }
+        assert_eq!(player.buffs.len(), 1);
+        assert_eq!(player.buffs[0].remaining, BuffKind::Damage.duration());
+        assert_eq!(player.buff_stacks(BuffKind::Damage), 2);
+        assert_eq!(player.stats.damage, 3);
+        // each kind stacks on its own
+        for _ in 0..5 {
+            player.add_buff(BuffKind::Speed);
+        }
+        assert_eq!(player.buff_stacks(BuffKind::Speed), 3);
+        assert!((player.stats.speed - 2.0 * 1.1_f32.powi(3)).abs() < 0.001);
+        assert_eq!(player.buff_stacks(BuffKind::Damage), 2);
+    }
+
+    #[test]
+    fn expired_buffs_give_back_the_base_stats() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut player = Player::new(0, &platform);
+        player.add_buff(BuffKind::Speed);
+        player.add_buff(BuffKind::Damage);
+        // the damage boost runs out first
+        idle(&mut player, BuffKind::Damage.duration(), &mut platform);
+        assert_eq!(player.buff_stacks(BuffKind::Damage), 0);
+        assert_eq!(player.stats.damage, 1);
+        assert!(player.stats.speed > 2.0);
+        assert!(player.notifications.contains(&"DAMAGE BOOST ENDED".to_string()));
+        idle(&mut player, BuffKind::Speed.duration() - BuffKind::Damage.duration(), &mut platform);
+        assert!(player.buffs.is_empty());
+        assert_eq!(player.stats.speed, 2.0);
+    }
+}
@@ -0,0 +1,262 @@
+use super::*;
+
+// Shape an entity collides as, fitted inside its hitbox
+#[turbo::serialize]
+#[derive(PartialEq, Copy, Default)]
+pub enum Shape {
+    #[default]
+    Rect, // the whole hitbox
+    Circle, // the largest circle centered in the hitbox, for round things like meteors and bullets
+    Capsule, // a line with rounded ends along the long side of the hitbox, for beams
+}
+
+// A shape placed on screen, with float precision, used for the actual collision tests
+// Built from an entity's hitbox and shape whenever it's needed, so it's never stored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collider {
+    Rect { x: f32, y: f32, w: f32, h: f32 },
+    Circle { x: f32, y: f32, r: f32 }, // center and radius
+    Capsule { a: (f32, f32), b: (f32, f32), r: f32 }, // every point within r of the segment from a to b
+}
+
+impl Collider {
+    pub fn new(hitbox: &Hitbox, shape: Shape) -> Self {
+        let (x, y, w, h) = (hitbox.x, hitbox.y, hitbox.w as f32, hitbox.h as f32);
+        match shape {
+            Shape::Rect => Collider::Rect { x, y, w, h },
+            Shape::Circle => Collider::Circle {
+                x: x + w / 2.0,
+                y: y + h / 2.0,
+                r: w.min(h) / 2.0,
+            },
+            Shape::Capsule if w >= h => {
+                let r = h / 2.0;
+                Collider::Capsule { a: (x + r, y + r), b: (x + w - r, y + r), r }
+            }
+            Shape::Capsule => {
+                let r = w / 2.0;
+                Collider::Capsule { a: (x + r, y + r), b: (x + r, y + h - r), r }
+            }
+        }
+    }
+
+    // The area covered while moving by (dx, dy) this frame, ending up at the current position
+    // Testing against this instead of the current position means fast projectiles can't skip over small targets
+    // A moving circle sweeps out a capsule, rects are widened to the box around both positions
+    // Capsules are only stretched along their own segment, so they're exact when moving along it
+    // and miss the sides of the swept area otherwise, fine for beams, which never sweep anyway
+    pub fn swept(self, dx: f32, dy: f32) -> Self {
+        match self {
+            Collider::Circle { x, y, r } => Collider::Capsule { a: (x - dx, y - dy), b: (x, y), r },
+            Collider::Rect { x, y, w, h } => Collider::Rect {
+                x: x - dx.max(0.0),
+                y: y - dy.max(0.0),
+                w: w + dx.abs(),
+                h: h + dy.abs(),
+            },
+            Collider::Capsule { a, b, r } => {
+                // the segment is stretched back to where it started, exact when moving along the segment
+                let start = (a.0 - dx, a.1 - dy);
+                if distance_squared(start, b) > distance_squared(a, b) {
+                    Collider::Capsule { a: start, b, r }
+                } else {
+                    Collider::Capsule { a, b: (b.0 - dx, b.1 - dy), r }
+                }
+            }
+        }
+    }
+
+    // Whether the two shapes overlap, touching edges don't count
+    pub fn overlaps(&self, other: &Collider) -> bool {
+        match (*self, *other) {
+            (Collider::Rect { x, y, w, h }, Collider::Rect { x: x2, y: y2, w: w2, h: h2 }) => {
+                x < x2 + w2 && x + w > x2 && y < y2 + h2 && y + h > y2
+            }
+            (Collider::Circle { x, y, r }, _) => other.distance_to(x, y) < r,
+            (_, Collider::Circle { .. }) => other.overlaps(self),
+            (Collider::Capsule { a, b, r }, Collider::Capsule { a: a2, b: b2, r: r2 }) => {
+                segment_segment_distance(a, b, a2, b2) < r + r2
+            }
+            (Collider::Capsule { a, b, r }, Collider::Rect { x, y, w, h })
+            | (Collider::Rect { x, y, w, h }, Collider::Capsule { a, b, r }) => {
+                segment_rect_distance(a, b, (x, y, w, h)) < r
+            }
+        }
+    }
+
+    // Distance from a point to the closest point of the shape, 0 if the point is inside it
+    pub fn distance_to(&self, px: f32, py: f32) -> f32 {
+        match *self {
+            Collider::Rect { x, y, w, h } => point_rect_distance((px, py), (x, y, w, h)),
+            Collider::Circle { x, y, r } => (distance_squared((px, py), (x, y)).sqrt() - r).max(0.0),
+            Collider::Capsule { a, b, r } => (segment_point_distance(a, b, (px, py)) - r).max(0.0),
+        }
+    }
+
+    // Smallest hitbox containing the shape, for looking it up in a SpatialGrid
+    pub fn bounds(&self) -> Hitbox {
+        let (left, top, right, bottom) = match *self {
+            Collider::Rect { x, y, w, h } => (x, y, x + w, y + h),
+            Collider::Circle { x, y, r } => (x - r, y - r, x + r, y + r),
+            Collider::Capsule { a, b, r } => (a.0.min(b.0) - r, a.1.min(b.1) - r, a.0.max(b.0) + r, a.1.max(b.1) + r),
+        };
+        Hitbox {
+            x: left,
+            y: top,
+            w: (right - left).ceil().max(0.0) as u32,
+            h: (bottom - top).ceil().max(0.0) as u32,
+        }
+    }
+}
+
+fn distance_squared(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+// Distance from a point to a rect given as (x, y, w, h), 0 if the point is inside it
+fn point_rect_distance(p: (f32, f32), rect: (f32, f32, f32, f32)) -> f32 {
+    let (x, y, w, h) = rect;
+    let dx = (x - p.0).max(p.0 - (x + w)).max(0.0);
+    let dy = (y - p.1).max(p.1 - (y + h)).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Distance from a point to the segment from a to b
+fn segment_point_distance(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = abx * abx + aby * aby;
+    // how far along the segment the closest point is, from 0 at a to 1 at b
+    let t = if length_squared > 0.0 {
+        (((p.0 - a.0) * abx + (p.1 - a.1) * aby) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    distance_squared(p, (a.0 + abx * t, a.1 + aby * t)).sqrt()
+}
+
+// Which side of the line through a and b the point c is on, 0 if it's on the line
+fn orientation(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+fn segments_intersect(a: (f32, f32), b: (f32, f32), c: (f32, f32), d: (f32, f32)) -> bool {
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    // the ends of each segment are on opposite sides of the other, or one end touches the other segment
+    (o1 * o2 < 0.0 && o3 * o4 < 0.0)
+        || (o1 == 0.0 && segment_point_distance(a, b, c) == 0.0)
+        || (o2 == 0.0 && segment_point_distance(a, b, d) == 0.0)
+        || (o3 == 0.0 && segment_point_distance(c, d, a) == 0.0)
+        || (o4 == 0.0 && segment_point_distance(c, d, b) == 0.0)
+}
+
+// Shortest distance between the segments from a to b and from c to d
+// Segments that don't cross are closest at one of their ends
+fn segment_segment_distance(a: (f32, f32), b: (f32, f32), c: (f32, f32), d: (f32, f32)) -> f32 {
+    if segments_intersect(a, b, c, d) {
+        return 0.0;
+    }
+    segment_point_distance(a, b, c)
+        .min(segment_point_distance(a, b, d))
+        .min(segment_point_distance(c, d, a))
+        .min(segment_point_distance(c, d, b))
+}
+
+// Shortest distance between the segment from a to b and a rect given as (x, y, w, h), 0 if they overlap
+fn segment_rect_distance(a: (f32, f32), b: (f32, f32), rect: (f32, f32, f32, f32)) -> f32 {
+    let (x, y, w, h) = rect;
+    if point_rect_distance(a, rect) == 0.0 {
+        return 0.0;
+    }
+    let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)];
+    // otherwise the segment is closest to one of the rect's edges
+    (0..4)
+        .map(|i| segment_segment_distance(a, b, corners[i], corners[(i + 1) % 4]))
+        .fold(f32::MAX, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hitbox(x: f32, y: f32, w: u32, h: u32) -> Hitbox {
+        Hitbox { x, y, w, h }
+    }
+
+    #[test]
+    fn fast_projectile_cant_tunnel_through_a_meteor() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut rng = GameRng::new(1);
+        let meteor = Collider::new(&hitbox(100.0, 100.0, 8, 8), Shape::Circle);
+        // a 6px shot going straight up at 20px a frame, starting just below the meteor
+        let mut projectile = Projectile::new(101.0, 112.0, 20.0, -90.0, ProjectileType::Basic, ProjectileOwner::Player(0), &mut rng, &mut platform);
+        let start = Collider::new(&projectile.hitbox, projectile.shape);
+        assert!(!start.overlaps(&meteor));
+        projectile.hitbox.y -= 20.0;
+        // after a frame of movement it's past the meteor, and only the swept shape catches it
+        assert!(!Collider::new(&projectile.hitbox, projectile.shape).overlaps(&meteor));
+        assert!(projectile.collider().overlaps(&meteor));
+        assert!(meteor.overlaps(&projectile.collider()));
+    }
+
+    #[test]
+    fn swept_shapes_miss_targets_off_to_the_side() {
+        let meteor = Collider::new(&hitbox(100.0, 100.0, 8, 8), Shape::Circle);
+        let shot = Collider::new(&hitbox(120.0, 90.0, 6, 6), Shape::Circle).swept(0.0, -20.0);
+        assert!(!shot.overlaps(&meteor));
+        // a rect moving diagonally covers the box around where it started and ended
+        let rect = Collider::new(&hitbox(10.0, 10.0, 4, 4), Shape::Rect).swept(-20.0, 20.0);
+        assert_eq!(rect, Collider::Rect { x: 10.0, y: -10.0, w: 24.0, h: 24.0 });
+    }
+
+    #[test]
+    fn circle_against_rect_corners() {
+        let rect = Collider::new(&hitbox(0.0, 0.0, 10, 10), Shape::Rect);
+        // a circle of radius 2 centered diagonally off the corner: its box overlaps the rect, the circle doesn't
+        let near_corner = Collider::Circle { x: 11.5, y: 11.5, r: 2.0 };
+        assert!(check_collision(&near_corner.bounds(), &hitbox(0.0, 0.0, 10, 10)));
+        assert!(!near_corner.overlaps(&rect));
+        assert!(!rect.overlaps(&near_corner));
+        // moved in a little, it reaches the corner
+        let on_corner = Collider::Circle { x: 11.0, y: 11.0, r: 2.0 };
+        assert!(on_corner.overlaps(&rect));
+        // next to an edge the circle works like a box
+        assert!(Collider::Circle { x: 11.9, y: 5.0, r: 2.0 }.overlaps(&rect));
+        assert!(!Collider::Circle { x: 12.1, y: 5.0, r: 2.0 }.overlaps(&rect));
+        // and inside the rect it always overlaps
+        assert!(Collider::Circle { x: 5.0, y: 5.0, r: 0.5 }.overlaps(&rect));
+    }
+
+    #[test]
+    fn capsule_against_rect() {
+        // a vertical beam 4px wide from y 0 to 100
+        let beam = Collider::new(&hitbox(50.0, 0.0, 4, 100), Shape::Capsule);
+        assert_eq!(beam, Collider::Capsule { a: (52.0, 2.0), b: (52.0, 98.0), r: 2.0 });
+        // ships across it, beside it, and past its rounded end
+        assert!(beam.overlaps(&Collider::new(&hitbox(40.0, 50.0, 16, 16), Shape::Rect)));
+        assert!(beam.overlaps(&Collider::new(&hitbox(53.0, 50.0, 16, 16), Shape::Rect)));
+        assert!(!beam.overlaps(&Collider::new(&hitbox(55.0, 50.0, 16, 16), Shape::Rect)));
+        assert!(!beam.overlaps(&Collider::new(&hitbox(54.0, 99.0, 16, 16), Shape::Rect)));
+        // a rect entirely inside the beam
+        assert!(beam.overlaps(&Collider::new(&hitbox(51.0, 40.0, 1, 1), Shape::Rect)));
+        // the order doesn't matter
+        let ship = Collider::new(&hitbox(40.0, 50.0, 16, 16), Shape::Rect);
+        assert_eq!(beam.overlaps(&ship), ship.overlaps(&beam));
+    }
+
+    #[test]
+    fn touching_edges_dont_overlap() {
+        let rect = Collider::new(&hitbox(0.0, 0.0, 10, 10), Shape::Rect);
+        assert!(!rect.overlaps(&Collider::new(&hitbox(10.0, 0.0, 10, 10), Shape::Rect)));
+        assert!(!rect.overlaps(&Collider::new(&hitbox(0.0, 10.0, 10, 10), Shape::Rect)));
+        assert!(rect.overlaps(&Collider::new(&hitbox(9.5, 0.0, 10, 10), Shape::Rect)));
+        // circles touching a rect or each other
+        assert!(!Collider::Circle { x: 12.0, y: 5.0, r: 2.0 }.overlaps(&rect));
+        let circle = Collider::Circle { x: 0.0, y: 0.0, r: 2.0 };
+        assert!(!circle.overlaps(&Collider::Circle { x: 4.0, y: 0.0, r: 2.0 }));
+        assert!(circle.overlaps(&Collider::Circle { x: 3.9, y: 0.0, r: 2.0 }));
+        // a capsule touching a rect along its side
+        let beam = Collider::Capsule { a: (12.0, 0.0), b: (12.0, 10.0), r: 2.0 };
+        assert!(!beam.overlaps(&rect));
+    }
+}
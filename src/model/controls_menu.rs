@@ -0,0 +1,165 @@
+use super::*;
+
+// How long the controls screen waits for a button to bind, in frames
+const BIND_TIMEOUT: u32 = 60 * 5;
+
+// Rows on the controls screen, every action followed by these
+pub const CONTROLS_GAMEPAD_ROW: usize = Action::ALL.len();
+pub const CONTROLS_RESET_ROW: usize = Action::ALL.len() + 1;
+pub const CONTROLS_BACK_ROW: usize = Action::ALL.len() + 2;
+
+// Screen for rebinding the actions to other gamepad buttons, opened from the options
+// Each gamepad has its own bindings, the GAMEPAD row picks which ones are shown and changed
+#[turbo::serialize]
+pub struct ControlsMenu {
+    pub selected: usize, // an index into Action::ALL, or one of the rows after it
+    pub gamepad: usize, // gamepad whose bindings are being changed, below MAX_PLAYERS
+    pub waiting: Option<u32>, // frames left to press a button for the selected action, None when not rebinding
+}
+
+impl ControlsMenu {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        ControlsMenu {
+            selected: 0,
+            gamepad: 0,
+            waiting: None,
+        }
+    }
+
+    // The first gamepad moves the selection with up and down and picks a row with A, tapping a row with the pointer picks it straight away
+    // Picking an action waits for the next button pressed on the gamepad being changed and binds the action to it, any button counts
+    // Rebinding is called off by tapping the screen or waiting a few seconds
+    // Left and right, or tapping either half of the GAMEPAD row, switch to another gamepad's bindings
+    // B, or picking BACK, closes the screen
+    // Returns true once the screen is closed
+    pub fn update(&mut self, controls: &[Input], settings: &mut Settings, platform: &dyn Platform) -> bool {
+        let input = &controls[0];
+        if let Some(frames) = self.waiting {
+            let pressed = Button::ALL.into_iter().find(|&button| controls[self.gamepad].just_pressed(button));
+            self.waiting = frames.checked_sub(1);
+            if let Some(button) = pressed {
+                settings.bindings_mut(self.gamepad).bind(Action::ALL[self.selected], button);
+                self.waiting = None;
+            }
+            if input.pointer_just_pressed().is_some() {
+                self.waiting = None;
+            }
+            return false;
+        }
+
+        let count = CONTROLS_BACK_ROW + 1;
+        if input.just_pressed(Button::B) {
+            return true;
+        }
+        if input.just_pressed(Button::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.just_pressed(Button::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+        if self.selected == CONTROLS_GAMEPAD_ROW {
+            if input.just_pressed(Button::Left) {
+                self.switch_gamepad(-1);
+            }
+            if input.just_pressed(Button::Right) {
+                self.switch_gamepad(1);
+            }
+        }
+        let mut picked = input.just_pressed(Button::A);
+        if let Some((px, py)) = input.pointer_just_pressed() {
+            let (px, py) = (px as f32, py as f32);
+            let screen = platform.resolution();
+            let tapped = (0..count).find(|&index| {
+                let bounds = Self::bounds(index, screen);
+                px >= bounds.x && px < bounds.x + bounds.w as f32 && py >= bounds.y && py < bounds.y + bounds.h as f32
+            });
+            if let Some(index) = tapped {
+                self.selected = index;
+                let bounds = Self::bounds(index, screen);
+                match index {
+                    CONTROLS_GAMEPAD_ROW if px < bounds.x + bounds.w as f32 / 2.0 => self.switch_gamepad(-1),
+                    CONTROLS_GAMEPAD_ROW => self.switch_gamepad(1),
+                    _ => picked = true,
+                }
+            }
+        }
+        if picked {
+            match self.selected {
+                CONTROLS_GAMEPAD_ROW => self.switch_gamepad(1),
+                CONTROLS_RESET_ROW => *settings.bindings_mut(self.gamepad) = Bindings::default(),
+                CONTROLS_BACK_ROW => return true,
+                _ => self.waiting = Some(BIND_TIMEOUT),
+            }
+        }
+        false
+    }
+
+    // Moves on to the next or previous gamepad, wrapping around
+    fn switch_gamepad(&mut self, direction: i32) {
+        self.gamepad = (self.gamepad as i32 + direction).rem_euclid(MAX_PLAYERS as i32) as usize;
+    }
+
+    // Area of the screen a row is drawn in, and can be tapped in
+    pub fn bounds(index: usize, screen: (u32, u32)) -> Hitbox {
+        let (screen_w, screen_h) = screen;
+        Hitbox {
+            x: (screen_w / 2 - 96) as f32,
+            y: (screen_h / 2 - 112 + index as u32 * 16) as f32,
+            w: 192,
+            h: 14,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Presses a button on one gamepad for a frame, then lets go of it
+    fn tap(menu: &mut ControlsMenu, controls: &mut [Input; MAX_PLAYERS], gamepad: usize, button: Button, settings: &mut Settings) {
+        let platform = MockPlatform::new(1, vec![]);
+        let mut frame = InputFrame::default();
+        frame.set(button, true);
+        for frame in [frame, InputFrame::default()] {
+            for (index, input) in controls.iter_mut().enumerate() {
+                input.update(if index == gamepad { frame.clone() } else { InputFrame::default() });
+            }
+            menu.update(controls, settings, &platform);
+        }
+    }
+
+    #[test]
+    fn each_gamepad_is_rebound_separately() {
+        let mut menu = ControlsMenu::new();
+        let mut controls: [Input; MAX_PLAYERS] = Default::default();
+        let mut settings = Settings::default();
+        // up to the GAMEPAD row, over to the second gamepad, and down to MOVE UP
+        for _ in 0..3 {
+            tap(&mut menu, &mut controls, 0, Button::Up, &mut settings);
+        }
+        assert_eq!(menu.selected, CONTROLS_GAMEPAD_ROW);
+        tap(&mut menu, &mut controls, 0, Button::Right, &mut settings);
+        assert_eq!(menu.gamepad, 1);
+        for _ in 0..3 {
+            tap(&mut menu, &mut controls, 0, Button::Down, &mut settings);
+        }
+        tap(&mut menu, &mut controls, 0, Button::A, &mut settings);
+        assert!(menu.waiting.is_some());
+        // the first gamepad's buttons don't count while the second one is being rebound
+        tap(&mut menu, &mut controls, 0, Button::X, &mut settings);
+        assert!(menu.waiting.is_some());
+        tap(&mut menu, &mut controls, 1, Button::Y, &mut settings);
+        assert_eq!(menu.waiting, None);
+        assert_eq!(settings.bindings(1).button(Action::MoveUp), Button::Y);
+        assert_eq!(settings.bindings(0), Bindings::default());
+        // resetting only resets the gamepad being shown
+        settings.bindings_mut(0).bind(Action::Fire, Button::B);
+        tap(&mut menu, &mut controls, 0, Button::Up, &mut settings);
+        tap(&mut menu, &mut controls, 0, Button::Up, &mut settings);
+        assert_eq!(menu.selected, CONTROLS_RESET_ROW);
+        tap(&mut menu, &mut controls, 0, Button::A, &mut settings);
+        assert_eq!(settings.bindings(1), Bindings::default());
+        assert_eq!(settings.bindings(0).button(Action::Fire), Button::B);
+    }
+}
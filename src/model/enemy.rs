@@ -3,7 +3,9 @@ use super::*;
 #[turbo::serialize]
 // Struct for Enemies
 pub struct Enemy {
-    enemy_type: EnemyType,
+    pub key: String, // unique, randomly generated key used to attach beams to this enemy
+    pub enemy_type: String, // name of the EnemyDefinition the enemy was created from
+    sprite: String,
     strategy: EnemyStrategy,
     
     pub hitbox: Hitbox,
@@ -14,175 +16,171 @@ pub struct Enemy {
     pub angle: f32,
     
     hit_timer: u32, // used for drawing
+    timer: u32, // frames spent firing, used to time bullet patterns
     pub destroyed: bool,
-}
+    pub killer: usize, // index of the player who destroyed the enemy, who its drops are for
 
-// AI States for enemy behavior
-#[turbo::serialize]
-enum EnemyStrategy {
-    TargetPlayer(f32, f32, u32), // Moves down. Attacks with given intensity, speed, and size
-    ShootDown(f32, f32, u32),    // Moves down. Attacks with given intensity, speed, and size
-    MoveDown,                    // Moves down. Nothing fancy
-    RandomZigZag(f32),           // Moves in a random zig zag pattern with a given angle
+    shape: Shape, // shape the enemy collides as when it has no parts
+    parts: Vec<Hitbox>, // hitboxes relative to the top left of the enemy, the whole hitbox is used when empty
+    pub boss: Option<Boss>, // set for bosses, which fight in phases instead of following a strategy
 }
 
-// Different types of enemies
+// AI States for enemy behavior
 #[turbo::serialize]
-pub enum EnemyType {
-    Tank,
-    Shooter,
-    Turret,
-    Zipper,
-    Meteor,
+#[derive(Default)]
+pub enum EnemyStrategy {
+    TargetPlayer { intensity: f32, speed: f32, size: u32 }, // Moves down. Attacks the nearest player with given intensity, speed, and size
+    FireBeam { intensity: f32, width: u32, duration: u32 }, // Moves down. Fires a laser beam straight down with given intensity, width, and duration
+    #[default]
+    MoveDown,                                               // Moves down. Nothing fancy
+    RandomZigZag { angle: f32 },                            // Moves in a random zig zag pattern with a given angle
+    FirePattern { patterns: Vec<BulletPattern>, interval: u32 }, // Moves down. Fires the patterns together every interval frames
 }
 
 impl Enemy {
-    // Initialize different enemy types with different properties
-    pub fn new(enemy_type: EnemyType) -> Self {
-        let (screen_w, _) = resolution();
-        // Set initial properties based on enemy type
-        match enemy_type {
-            EnemyType::Tank => {
-                Self {
-                    enemy_type: EnemyType::Tank,
-                    strategy: EnemyStrategy::TargetPlayer(1.0, 2.5, 8),
-                    hitbox: Hitbox {
-                        x: (random::u32() % screen_w).saturating_sub(32) as f32,
-                        y: -32.0,
-                        w: 32,
-                        h: 32,
-                    },
-                    hp: 10,
-                    points: 50,
-                    speed: 0.25,
-                    angle: 0.0,
-                    destroyed: false,
-                    hit_timer: 0,
-                }
-            },
-            EnemyType::Shooter => {
-                Self {
-                    enemy_type: EnemyType::Shooter,
-                    strategy: EnemyStrategy::TargetPlayer(3.0, 2.0, 4),
-                    hitbox: Hitbox {
-                        x: (random::u32() % screen_w).saturating_sub(16) as f32,
-                        y: -16.0,
-                        w: 16,
-                        h: 16,
-                    },
-                    hp: 5,
-                    points: 30,
-                    speed: 1.0,
-                    angle: 0.0,
-                    destroyed: false,
-                    hit_timer: 0,
-                }
-            },
-            EnemyType::Turret => {
-                Self {
-                    enemy_type: EnemyType::Turret,
-                    strategy: EnemyStrategy::ShootDown(2.0, 2.5, 2),
-                    hitbox: Hitbox {
-                        x: (random::u32() % screen_w).saturating_sub(16) as f32,
-                        y: -8.0,
-                        w: 16,
-                        h: 16,
-                    },
-                    hp: 8,
-                    points: 30,
-                    speed: 0.5,
-                    angle: 0.0,
-                    destroyed: false,
-                    hit_timer: 0,
-                }
-            },
-            EnemyType::Zipper => {
-                Self {
-                    enemy_type: EnemyType::Zipper,
-                    strategy: EnemyStrategy::RandomZigZag(1.0),
-                    hitbox: Hitbox {
-                        x: (random::u32() % screen_w).saturating_sub(16) as f32,
-                        y: -16.0,
-                        w: 16,
-                        h: 16,
-                    },
-                    hp: 4,
-                    points: 20,
-                    speed: 0.5,
-                    angle: 0.0,
-                    destroyed: false,
-                    hit_timer: 0,
-                }
-            },
-            EnemyType::Meteor => {
-                Self {
-                    enemy_type: EnemyType::Meteor,
-                    strategy: EnemyStrategy::MoveDown,
-                    hitbox: Hitbox {
-                        x: (random::u32() % screen_w).saturating_sub(8) as f32,
-                        y: -8.0,
-                        w: 8,
-                        h: 8,
-                    },
-                    hp: 3,
-                    points: 20,
-                    speed: 1.0,
-                    angle: 0.0,
-                    destroyed: false,
-                    hit_timer: 0,
-                }
+    // Initialize an enemy from its definition in data/enemies.json
+    pub fn new(definition: &EnemyDefinition, rng: &mut GameRng, platform: &dyn Platform) -> Self {
+        let (screen_w, _) = platform.resolution();
+        Self {
+            key: rng.spawns.u32().to_string(),
+            enemy_type: definition.name.clone(),
+            sprite: definition.sprite.clone(),
+            strategy: definition.strategy.clone(),
+            // Enemies spawn at a random x position, just above the screen unless the definition says otherwise
+            hitbox: Hitbox {
+                x: (rng.spawns.u32() % screen_w).saturating_sub(definition.width) as f32,
+                y: definition.spawn_y.unwrap_or(-(definition.height as f32)),
+                w: definition.width,
+                h: definition.height,
             },
+            hp: definition.hp,
+            points: definition.points,
+            speed: definition.speed,
+            angle: 0.0,
+            destroyed: false,
+            killer: 0,
+            hit_timer: 0,
+            timer: 0,
+            shape: definition.shape,
+            parts: definition.parts.clone(),
+            boss: definition.boss.as_ref().map(|boss| Boss::new(boss, definition.hp)),
         }
     }
 
+    // Whether the collider overlaps any part of the enemy
+    pub fn collides(&self, collider: &Collider) -> bool {
+        self.colliders().any(|part| part.overlaps(collider))
+    }
+
+    // Distance from a point to the closest part of the enemy, 0 if the point is inside it
+    pub fn distance_to(&self, x: f32, y: f32) -> f32 {
+        self.colliders()
+            .map(|part| part.distance_to(x, y))
+            .fold(f32::MAX, f32::min)
+    }
+
+    // Shapes of the enemy's parts on screen, or of the whole enemy if it has no parts
+    fn colliders(&self) -> impl Iterator<Item = Collider> + '_ {
+        let whole = self.parts.is_empty().then(|| Collider::new(&self.hitbox, self.shape));
+        whole.into_iter().chain(self.parts.iter().map(|part| {
+            let hitbox = Hitbox {
+                x: self.hitbox.x + part.x,
+                y: self.hitbox.y + part.y,
+                w: part.w,
+                h: part.h,
+            };
+            Collider::new(&hitbox, Shape::Rect)
+        }))
+    }
+
     // update is called once per frame within the [turbo::game] loop
-    pub fn update(&mut self, player: &mut Player, projectiles: &mut Vec<Projectile>){
-        let (screen_w, screen_h) = resolution();
+    pub fn update(&mut self, players: &[Player], projectiles: &mut Vec<Projectile>, rng: &mut GameRng, platform: &mut dyn Platform) {
+        let (screen_w, screen_h) = platform.resolution();
+
+        // Bosses follow the phases of their fight instead of a strategy
+        if let Some(boss) = &mut self.boss {
+            if let Some(volley) = boss.update(&mut self.hitbox, self.hp, self.speed, platform) {
+                let origin = (
+                    self.hitbox.x + self.hitbox.w as f32 / 2.0,
+                    self.hitbox.y + self.hitbox.h as f32 / 2.0,
+                );
+                for pattern in &boss.phase().patterns {
+                    for mut projectile in pattern.fire(origin, players, volley, rng, platform) {
+                        projectile.source = Some(self.enemy_type.clone());
+                        projectiles.push(projectile);
+                    }
+                }
+            }
+            self.hit_timer = self.hit_timer.saturating_sub(1);
+            return;
+        }
 
         // Logic for different enemy strategies
         match self.strategy {
-            EnemyStrategy::TargetPlayer(intensity, speed, size) => {
+            EnemyStrategy::TargetPlayer { intensity, speed, size } => {
                 self.hitbox.y += self.speed;
                 // Logic for attacking with specified intensity
-                if random::u32() % (250 / intensity as u32) == 0 {
-                    // Calculate angle from self to player
-                    let angle = ((player.hitbox.y - self.hitbox.y).atan2(player.hitbox.x - self.hitbox.x)
-                        * 180.0)
-                        / std::f32::consts::PI;
-
-                    // Create and shoot projectiles from enemy towards the player
-                    projectiles.push(
-                        Projectile::new(
-                            self.hitbox.x + (self.hitbox.w as f32 * 0.5) - (size as f32 * 0.5),
-                            self.hitbox.y + (self.hitbox.h as f32),
-                            speed,
-                            angle,
-                            // damage: intensity as u32, // Damage based on attack intensity
-                            ProjectileType::Laser, // Assuming enemy uses Laser
-                            ProjectileOwner::Enemy,
-                        )
+                if rng.ai.u32().is_multiple_of(250 / intensity as u32) {
+                    // Shoot a single bullet of the given size from the front of the enemy towards the nearest player
+                    let pattern = BulletPattern::aimed(speed);
+                    let origin = (
+                        self.hitbox.x + self.hitbox.w as f32 * 0.5,
+                        self.hitbox.y + self.hitbox.h as f32 + size as f32 * 0.5,
                     );
+                    for mut projectile in pattern.fire(origin, players, 0, rng, platform) {
+                        projectile.apply_stats(projectile.damage, size, projectile.pierce);
+                        projectile.source = Some(self.enemy_type.clone());
+                        projectiles.push(projectile);
+                    }
                 }
             }
-            EnemyStrategy::ShootDown(intensity, speed, size) => {
-                // Logic for attacking with specified intensity
+            EnemyStrategy::FireBeam { intensity, width, duration } => {
                 self.hitbox.y += self.speed;
-                if random::u32() % (250 / intensity as u32) == 0 {
-                    // Create and shoot projectiles from enemy towards the player
-                    projectiles.push(Projectile::new(
-                        self.hitbox.x + (self.hitbox.w as f32 * 0.5) - (size as f32 * 0.5),
-                        self.hitbox.y + (self.hitbox.h as f32),
-                        speed,
-                        90.0,
-                        ProjectileType::Laser,
+                // Only one beam at a time, and only once the enemy is on screen
+                let firing = projectiles.iter().any(|projectile| {
+                    projectile.beam.as_ref().is_some_and(|beam| beam.anchor == self.key)
+                });
+                if !firing
+                && self.hitbox.y > 0.0
+                && rng.ai.u32().is_multiple_of(250 / intensity as u32) {
+                    // Telegraph the beam for 45 frames before it turns lethal
+                    let mut beam = Projectile::new_beam(
+                        &self.key,
+                        width,
+                        45,
+                        duration,
                         ProjectileOwner::Enemy,
-                    ));
+                        rng,
+                        platform,
+                    );
+                    beam.source = Some(self.enemy_type.clone());
+                    projectiles.push(beam);
+                }
+            }
+            EnemyStrategy::FirePattern { ref patterns, interval } => {
+                self.hitbox.y += self.speed;
+                // Fire every interval frames once on screen
+                if self.hitbox.y > 0.0 {
+                    if self.timer.is_multiple_of(interval) {
+                        let origin = (
+                            self.hitbox.x + self.hitbox.w as f32 / 2.0,
+                            self.hitbox.y + self.hitbox.h as f32 / 2.0,
+                        );
+                        let volley = self.timer / interval;
+                        for pattern in patterns {
+                            for mut projectile in pattern.fire(origin, players, volley, rng, platform) {
+                                projectile.source = Some(self.enemy_type.clone());
+                                projectiles.push(projectile);
+                            }
+                        }
+                    }
+                    self.timer += 1;
                 }
             }
             EnemyStrategy::MoveDown => {
                 self.hitbox.y += self.speed;
             }
-            EnemyStrategy::RandomZigZag(angle) => {
+            EnemyStrategy::RandomZigZag { angle } => {
                 // Logic for dodging attacks, using angle to determine movement
                 self.hitbox.x += self.speed * self.angle.cos();
                 self.hitbox.y += self.speed;
@@ -191,7 +189,7 @@ impl Enemy {
                     self.angle = std::f32::consts::PI - self.angle;
                 }
                 // 5% chance to randomly change angle
-                else if random::u32() % 20 == 0 {
+                else if rng.ai.u32().is_multiple_of(20) {
                     self.angle += std::f32::consts::PI / angle; // Change angle
                 }
             }
@@ -205,29 +203,38 @@ impl Enemy {
     }
 
     pub fn take_damage(&mut self, player: &mut Player, damage: u32) {
+        // Bosses can't be hurt until they've finished flying in
+        if self.boss.as_ref().is_some_and(|boss| boss.entering) {
+            return;
+        }
         self.hp = self.hp.saturating_sub(damage);
         self.hit_timer = 5; // frames to show hit effect
         if self.hp == 0 {
             self.destroyed = true;
+            self.killer = player.index;
             player.score += self.points;
+            player.run_stats.record_kill(&self.enemy_type);
+        }
+    }
+
+    // Sets the enemy's full HP, for bosses too
+    pub fn set_max_hp(&mut self, hp: u32) {
+        self.hp = hp;
+        if let Some(boss) = &mut self.boss {
+            boss.max_hp = hp;
         }
     }
 
-    pub fn draw(&self) {
-        let mut sprite = match self.enemy_type {
-            EnemyType::Tank => "enemies/tank",
-            EnemyType::Shooter => "enemies/shooter",
-            EnemyType::Turret => "enemies/turret",
-            EnemyType::Zipper => "enemies/zipper",
-            EnemyType::Meteor => "enemies/meteor",
-        };
+    // flash_color is what the enemy is tinted while flashing from a hit
+    pub fn draw(&self, flash_color: u32) {
+        let sprite = self.sprite.as_str();
 
-        if self.hit_timer > 0 && (self.hit_timer / 4) % 2 == 0 {
+        if self.hit_timer > 0 && (self.hit_timer / 4).is_multiple_of(2) {
             sprite!(
                 &sprite,
                 x = self.hitbox.x,
                 y = self.hitbox.y,
-                color = 0xff0000ff,
+                color = flash_color,
             );
         } else {
             sprite!(
@@ -0,0 +1,237 @@
+use super::*;
+
+// Enemy archetypes, embedded at build time so designers can add and tweak enemies without touching Rust
+const ENEMIES_JSON: &str = include_str!("../../data/enemies.json");
+
+// Stats and behavior for one type of enemy, as defined in data/enemies.json
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub struct EnemyDefinition {
+    pub name: String, // unique name, also used by the balance simulator's statistics
+    pub sprite: String,
+    pub width: u32,
+    pub height: u32,
+    pub spawn_y: Option<f32>, // starting y position, defaults to just above the screen
+    pub hp: u32,
+    pub points: u32, // score awarded for destroying the enemy
+    pub speed: f32,
+    #[serde(default)]
+    pub shape: Shape, // shape the enemy collides as, parts are always rects
+    #[serde(default)]
+    pub strategy: EnemyStrategy, // ignored for bosses
+    pub spawn_weight: u32, // relative chance of being picked when an enemy spawns, 0 to never spawn
+    #[serde(default)]
+    pub parts: Vec<Hitbox>, // hitboxes relative to the top left of the sprite, for ships that don't fill their whole sprite
+    pub boss: Option<BossDefinition>, // set for bosses, which are spawned by a level's Boss step
+}
+
+// Problems found while loading enemy definitions
+#[derive(Debug)]
+pub enum EnemyDefinitionError {
+    Parse(String), // the file isn't valid JSON or doesn't match the EnemyDefinition fields
+    Empty,
+    MissingName(usize), // index of the entry
+    DuplicateName(String),
+    InvalidSize(String),
+    InvalidHp(String),
+    InvalidSpeed(String),
+    InvalidIntensity(String), // intensity must be within 1-250, see Enemy::update
+    NothingToSpawn, // every spawn weight is 0
+    InvalidParts(String), // a part is empty or sticks out of the sprite
+    InvalidBoss(String), // see BossDefinition::is_valid, bosses also need a speed and can't spawn at random
+    InvalidPattern(String), // see BulletPattern::is_valid
+    InvalidAttack(String), // a TargetPlayer shot or FireBeam that wouldn't show up or move
+}
+
+impl std::fmt::Display for EnemyDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EnemyDefinitionError::Parse(err) => write!(f, "couldn't parse enemy definitions: {}", err),
+            EnemyDefinitionError::Empty => write!(f, "no enemies are defined"),
+            EnemyDefinitionError::MissingName(index) => write!(f, "enemy #{} has no name", index),
+            EnemyDefinitionError::DuplicateName(name) => write!(f, "enemy \"{}\" is defined more than once", name),
+            EnemyDefinitionError::InvalidSize(name) => write!(f, "enemy \"{}\" needs a width and height above 0", name),
+            EnemyDefinitionError::InvalidHp(name) => write!(f, "enemy \"{}\" needs hp above 0", name),
+            EnemyDefinitionError::InvalidSpeed(name) => write!(f, "enemy \"{}\" has a negative or invalid speed", name),
+            EnemyDefinitionError::InvalidIntensity(name) => write!(f, "enemy \"{}\" needs an attack intensity from 1 to 250", name),
+            EnemyDefinitionError::NothingToSpawn => write!(f, "every enemy has a spawn_weight of 0"),
+            EnemyDefinitionError::InvalidParts(name) => write!(f, "enemy \"{}\" has a part that is empty or outside of its width and height", name),
+            EnemyDefinitionError::InvalidPattern(name) => write!(f, "enemy \"{}\" needs an interval above 0 and bullet patterns with a count and speed above 0", name),
+            EnemyDefinitionError::InvalidAttack(name) => write!(f, "enemy \"{}\" needs a shot speed and size, or a beam width and duration, above 0", name),
+            EnemyDefinitionError::InvalidBoss(name) => write!(f, "enemy \"{}\" is a boss, so it needs a speed above 0, a spawn_weight of 0, and phases starting at 100 hp with decreasing thresholds and attacks that fire", name),
+        }
+    }
+}
+
+impl EnemyDefinition {
+    // Loads the enemy definitions embedded from data/enemies.json
+    pub fn load() -> Result<Vec<EnemyDefinition>, EnemyDefinitionError> {
+        EnemyDefinition::parse(ENEMIES_JSON)
+    }
+
+    // Parses and validates a JSON list of enemy definitions
+    pub fn parse(json: &str) -> Result<Vec<EnemyDefinition>, EnemyDefinitionError> {
+        let definitions: Vec<EnemyDefinition> = serde_json::from_str(json)
+            .map_err(|err| EnemyDefinitionError::Parse(err.to_string()))?;
+        if definitions.is_empty() {
+            return Err(EnemyDefinitionError::Empty);
+        }
+        for (i, definition) in definitions.iter().enumerate() {
+            definition.validate(i)?;
+            if definitions[..i].iter().any(|other| other.name == definition.name) {
+                return Err(EnemyDefinitionError::DuplicateName(definition.name.clone()));
+            }
+        }
+        if definitions.iter().all(|definition| definition.spawn_weight == 0) {
+            return Err(EnemyDefinitionError::NothingToSpawn);
+        }
+        Ok(definitions)
+    }
+
+    fn validate(&self, index: usize) -> Result<(), EnemyDefinitionError> {
+        let name = self.name.clone();
+        if name.trim().is_empty() {
+            return Err(EnemyDefinitionError::MissingName(index));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err(EnemyDefinitionError::InvalidSize(name));
+        }
+        if self.hp == 0 {
+            return Err(EnemyDefinitionError::InvalidHp(name));
+        }
+        if !self.speed.is_finite() || self.speed < 0.0 {
+            return Err(EnemyDefinitionError::InvalidSpeed(name));
+        }
+        let outside = |part: &Hitbox| {
+            part.w == 0
+                || part.h == 0
+                || part.x < 0.0
+                || part.y < 0.0
+                || part.x + part.w as f32 > self.width as f32
+                || part.y + part.h as f32 > self.height as f32
+        };
+        if self.parts.iter().any(outside) {
+            return Err(EnemyDefinitionError::InvalidParts(name));
+        }
+        // bosses have to be able to finish flying in, and are only spawned by a level's Boss step
+        if self.boss.as_ref().is_some_and(|boss| !boss.is_valid() || self.speed <= 0.0 || self.spawn_weight > 0) {
+            return Err(EnemyDefinitionError::InvalidBoss(name));
+        }
+        // attacks roll a 1 in (250 / intensity) chance every frame, which is only defined from 1 to 250
+        match self.strategy {
+            EnemyStrategy::TargetPlayer { intensity, .. } | EnemyStrategy::FireBeam { intensity, .. }
+                if !(1.0..=250.0).contains(&intensity) =>
+            {
+                Err(EnemyDefinitionError::InvalidIntensity(name))
+            }
+            EnemyStrategy::FirePattern { ref patterns, interval }
+                if interval == 0 || patterns.is_empty() || !patterns.iter().all(BulletPattern::is_valid) =>
+            {
+                Err(EnemyDefinitionError::InvalidPattern(name))
+            }
+            EnemyStrategy::TargetPlayer { speed, size, .. } if size == 0 || !BulletPattern::aimed(speed).is_valid() => {
+                Err(EnemyDefinitionError::InvalidAttack(name))
+            }
+            EnemyStrategy::FireBeam { width, duration, .. } if width == 0 || duration == 0 => {
+                Err(EnemyDefinitionError::InvalidAttack(name))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Picks a random definition, weighted by spawn_weight
+    pub fn pick<'a>(definitions: &'a [EnemyDefinition], rng: &mut GameRng) -> &'a EnemyDefinition {
+        let total: u32 = definitions.iter().map(|definition| definition.spawn_weight).sum();
+        let mut roll = rng.spawns.u32() % total;
+        for definition in definitions {
+            if roll < definition.spawn_weight {
+                return definition;
+            }
+            roll -= definition.spawn_weight;
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shooter(intensity: f32) -> String {
+        format!("[{}]", shooter_entry(intensity))
+    }
+
+    fn shooter_entry(intensity: f32) -> String {
+        format!(
+            r#"{{
+                "name": "shooter",
+                "sprite": "enemies/shooter",
+                "width": 16,
+                "height": 16,
+                "hp": 5,
+                "points": 30,
+                "speed": 1.0,
+                "strategy": {{ "TargetPlayer": {{ "intensity": {}, "speed": 2.0, "size": 4 }} }},
+                "spawn_weight": 1
+            }}"#,
+            intensity
+        )
+    }
+
+    #[test]
+    fn embedded_definitions_are_valid() {
+        assert!(EnemyDefinition::load().is_ok());
+    }
+
+    #[test]
+    fn bosses_need_a_speed_and_no_spawn_weight() {
+        let boss = EnemyDefinition::load().unwrap().into_iter().find(|definition| definition.boss.is_some()).unwrap();
+        // alongside an enemy that spawns, so there's something to spawn
+        let parse = |boss: &EnemyDefinition| EnemyDefinition::parse(&format!("[{}, {}]", shooter_entry(1.0), serde_json::to_string(boss).unwrap()));
+        assert!(parse(&boss).is_ok());
+        // a boss that never finishes flying in can't be hurt, and one picked at random has no Boss step holding the level
+        for (speed, spawn_weight) in [(0.0, 0), (0.5, 1)] {
+            let broken = EnemyDefinition { speed, spawn_weight, ..boss.clone() };
+            assert!(matches!(parse(&broken), Err(EnemyDefinitionError::InvalidBoss(_))), "speed {} weight {}", speed, spawn_weight);
+        }
+    }
+
+    #[test]
+    fn intensity_must_be_from_1_to_250() {
+        for intensity in [1.0, 3.0, 250.0] {
+            assert!(EnemyDefinition::parse(&shooter(intensity)).is_ok(), "intensity {}", intensity);
+        }
+        // below 1, 250 / intensity as u32 would divide by zero
+        for intensity in [0.0, 0.5, 0.999, 250.5, -1.0] {
+            assert!(
+                matches!(EnemyDefinition::parse(&shooter(intensity)), Err(EnemyDefinitionError::InvalidIntensity(_))),
+                "intensity {}",
+                intensity
+            );
+        }
+    }
+
+    #[test]
+    fn shots_and_beams_need_a_size_and_speed() {
+        let attack = |strategy: &str| shooter(1.0).replace(r#"{ "TargetPlayer": { "intensity": 1, "speed": 2.0, "size": 4 } }"#, strategy);
+        for strategy in [
+            r#"{ "TargetPlayer": { "intensity": 1.0, "speed": 2.0, "size": 4 } }"#,
+            r#"{ "FireBeam": { "intensity": 1.0, "width": 4, "duration": 60 } }"#,
+        ] {
+            assert!(EnemyDefinition::parse(&attack(strategy)).is_ok(), "{}", strategy);
+        }
+        for strategy in [
+            r#"{ "TargetPlayer": { "intensity": 1.0, "speed": 0.0, "size": 4 } }"#,
+            r#"{ "TargetPlayer": { "intensity": 1.0, "speed": -2.0, "size": 4 } }"#,
+            r#"{ "TargetPlayer": { "intensity": 1.0, "speed": 2.0, "size": 0 } }"#,
+            r#"{ "FireBeam": { "intensity": 1.0, "width": 0, "duration": 60 } }"#,
+            r#"{ "FireBeam": { "intensity": 1.0, "width": 4, "duration": 0 } }"#,
+        ] {
+            assert!(
+                matches!(EnemyDefinition::parse(&attack(strategy)), Err(EnemyDefinitionError::InvalidAttack(_))),
+                "{}",
+                strategy
+            );
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use super::*;
+
+// Uniform grid over the screen for finding which hitboxes are near each other
+// Each cell lists the indices of the entities overlapping it, so a query only has to look at
+// the entities in the cells it covers instead of every entity in the game
+// Entities outside the screen, like enemies flying in from the top, are kept in the cells along the edge
+#[turbo::serialize]
+pub struct SpatialGrid {
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>, // row by row, indices into the list of entities the grid was built from
+}
+
+// Size of a grid cell in pixels, about the size of the bigger enemies
+const CELL_SIZE: f32 = 32.0;
+
+impl SpatialGrid {
+    pub fn new(platform: &dyn Platform) -> Self {
+        let (screen_w, screen_h) = platform.resolution();
+        let columns = ((screen_w as f32 / CELL_SIZE).ceil() as usize).max(1);
+        let rows = ((screen_h as f32 / CELL_SIZE).ceil() as usize).max(1);
+        SpatialGrid {
+            cell_size: CELL_SIZE,
+            columns,
+            rows,
+            cells: vec![vec![]; columns * rows],
+        }
+    }
+
+    // Replaces the contents of the grid with the given hitboxes, in order
+    pub fn build<'a>(&mut self, hitboxes: impl Iterator<Item = &'a Hitbox>) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+        for (index, hitbox) in hitboxes.enumerate() {
+            let (x0, x1, y0, y1) = self.cell_range(hitbox);
+            for row in y0..=y1 {
+                for column in x0..=x1 {
+                    self.cells[row * self.columns + column].push(index);
+                }
+            }
+        }
+    }
+
+    // Indices of the entities that might overlap the hitbox, in ascending order
+    // Still needs an exact check, entities sharing a cell with the hitbox don't always touch it
+    pub fn query(&self, hitbox: &Hitbox) -> Vec<usize> {
+        let (x0, x1, y0, y1) = self.cell_range(hitbox);
+        let mut indices = vec![];
+        for row in y0..=y1 {
+            for column in x0..=x1 {
+                indices.extend_from_slice(&self.cells[row * self.columns + column]);
+            }
+        }
+        // entities spanning several cells show up more than once
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    // First and last column and row covered by the hitbox, clamped to the grid
+    fn cell_range(&self, hitbox: &Hitbox) -> (usize, usize, usize, usize) {
+        let cell = |position: f32, count: usize| ((position / self.cell_size).floor().max(0.0) as usize).min(count - 1);
+        (
+            cell(hitbox.x, self.columns),
+            cell(hitbox.x + hitbox.w as f32, self.columns),
+            cell(hitbox.y, self.rows),
+            cell(hitbox.y + hitbox.h as f32, self.rows),
+        )
+    }
+}
+
+// The grids used for collision checks, rebuilt by the GameState every frame
+#[turbo::serialize]
+pub struct Grids {
+    pub enemies: SpatialGrid,
+    pub powerups: SpatialGrid,
+}
+
+impl Grids {
+    pub fn new(platform: &dyn Platform) -> Self {
+        Grids {
+            enemies: SpatialGrid::new(platform),
+            powerups: SpatialGrid::new(platform),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hitboxes of all sizes scattered over and just past the edges of a 256x512 screen
+    fn scatter(rng: &mut Rng, count: usize) -> Vec<Hitbox> {
+        (0..count)
+            .map(|_| Hitbox {
+                x: (rng.u32() % 320) as f32 - 32.0,
+                y: (rng.u32() % 576) as f32 - 32.0,
+                w: 1 + rng.u32() % 64,
+                h: 1 + rng.u32() % 64,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn queries_find_everything_a_brute_force_search_does() {
+        let platform = MockPlatform::new(1, vec![]);
+        let mut rng = Rng::new(1, 1);
+        let mut grid = SpatialGrid::new(&platform);
+        let mut skipped = 0;
+        for _ in 0..20 {
+            let hitboxes = scatter(&mut rng, 100);
+            grid.build(hitboxes.iter());
+            for query in scatter(&mut rng, 50) {
+                let found = grid.query(&query);
+                let expected: Vec<usize> = (0..hitboxes.len()).filter(|&i| check_collision(&hitboxes[i], &query)).collect();
+                assert!(expected.iter().all(|i| found.contains(i)), "{:?} missing from {:?}", expected, found);
+                // in order and without duplicates
+                assert!(found.windows(2).all(|pair| pair[0] < pair[1]));
+                skipped += hitboxes.len() - found.len();
+            }
+        }
+        // and it leaves out most of the far away ones
+        assert!(skipped > 20 * 50 * 100 / 2);
+    }
+
+    #[test]
+    fn rebuilding_forgets_the_old_hitboxes() {
+        let platform = MockPlatform::new(1, vec![]);
+        let mut grid = SpatialGrid::new(&platform);
+        let corner = Hitbox { x: 0.0, y: 0.0, w: 8, h: 8 };
+        grid.build([corner.clone(), Hitbox { x: 200.0, y: 400.0, w: 8, h: 8 }].iter());
+        assert_eq!(grid.query(&corner), [0]);
+        grid.build(std::iter::empty());
+        assert!(grid.query(&corner).is_empty());
+    }
+}
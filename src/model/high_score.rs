@@ -0,0 +1,166 @@
+use super::*;
+
+// Number of scores kept on the table
+const MAX_HIGH_SCORES: usize = 10;
+
+#[turbo::serialize]
+pub struct HighScore {
+    pub initials: String,
+    pub score: u32,
+    pub level: String, // name of the level the score was set on
+}
+
+// The best scores so far, highest first
+#[turbo::serialize]
+#[derive(Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    // Whether the score is good enough to make the table
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_HIGH_SCORES
+                || self.entries.last().is_some_and(|entry| score > entry.score))
+    }
+
+    // Adds a score to the table, dropping the lowest one if it's full
+    // Returns the score's position on the table, ties go below the scores that were there first
+    pub fn insert(&mut self, entry: HighScore) -> usize {
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        rank
+    }
+
+    // Top score on the table, 0 when it's empty
+    pub fn best(&self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+}
+
+// Three letter initials being entered for a new high score
+#[turbo::serialize]
+pub struct NameEntry {
+    pub letters: [u8; 3], // 0 to 25, A to Z
+    pub cursor: usize, // index of the letter being changed
+}
+
+impl NameEntry {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        NameEntry {
+            letters: [0; 3],
+            cursor: 0,
+        }
+    }
+
+    // Up and down change the current letter, left and right or A and B move between letters
+    // Returns true once the initials are confirmed, with START or A on the last letter
+    pub fn update(&mut self, input: &Input) -> bool {
+        let letter = &mut self.letters[self.cursor];
+        if input.just_pressed(Button::Up) {
+            *letter = (*letter + 1) % 26;
+        }
+        if input.just_pressed(Button::Down) {
+            *letter = (*letter + 25) % 26;
+        }
+        if input.just_pressed(Button::Start) || (input.just_pressed(Button::A) && self.cursor == 2) {
+            return true;
+        }
+        if input.just_pressed(Button::Right) || input.just_pressed(Button::A) {
+            self.cursor = (self.cursor + 1).min(2);
+        }
+        if input.just_pressed(Button::Left) || input.just_pressed(Button::B) {
+            self.cursor = self.cursor.saturating_sub(1);
+        }
+        false
+    }
+
+    pub fn initials(&self) -> String {
+        self.letters.iter().map(|letter| (b'A' + letter) as char).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(initials: &str, score: u32) -> HighScore {
+        HighScore {
+            initials: initials.to_string(),
+            score,
+            level: "SECTOR 1".to_string(),
+        }
+    }
+
+    fn scores(table: &HighScores) -> Vec<u32> {
+        table.entries.iter().map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn any_score_makes_a_table_with_room_left() {
+        let mut table = HighScores::default();
+        assert!(!table.qualifies(0));
+        assert!(table.qualifies(1));
+        assert_eq!(table.best(), 0);
+        for score in [50, 200, 100] {
+            table.insert(entry("AAA", score));
+        }
+        assert!(table.qualifies(1));
+        assert_eq!(scores(&table), [200, 100, 50]);
+        assert_eq!(table.best(), 200);
+    }
+
+    #[test]
+    fn full_tables_only_take_scores_beating_the_lowest() {
+        let mut table = HighScores::default();
+        for score in 1..=10 {
+            table.insert(entry("AAA", score * 100));
+        }
+        assert!(!table.qualifies(100));
+        assert!(table.qualifies(101));
+        assert_eq!(table.insert(entry("BBB", 550)), 5);
+        assert_eq!(scores(&table), [1000, 900, 800, 700, 600, 550, 500, 400, 300, 200]);
+    }
+
+    #[test]
+    fn ties_go_below_the_scores_already_there() {
+        let mut table = HighScores::default();
+        table.insert(entry("AAA", 300));
+        table.insert(entry("BBB", 100));
+        assert_eq!(table.insert(entry("CCC", 300)), 1);
+        let initials: Vec<&str> = table.entries.iter().map(|entry| entry.initials.as_str()).collect();
+        assert_eq!(initials, ["AAA", "CCC", "BBB"]);
+    }
+
+    #[test]
+    fn initials_are_picked_letter_by_letter() {
+        let mut input = Input::default();
+        let mut name = NameEntry::new();
+        let mut press = |name: &mut NameEntry, button: Button| {
+            let mut frame = InputFrame::default();
+            frame.set(button, true);
+            input.update(frame);
+            let done = name.update(&input);
+            input.update(InputFrame::default());
+            done
+        };
+        // down wraps around to Z, A moves on to the next letter
+        press(&mut name, Button::Down);
+        press(&mut name, Button::A);
+        press(&mut name, Button::Up);
+        press(&mut name, Button::Up);
+        press(&mut name, Button::Right);
+        press(&mut name, Button::Right);
+        assert_eq!(name.cursor, 2);
+        press(&mut name, Button::Up);
+        assert_eq!(name.initials(), "ZCB");
+        assert!(press(&mut name, Button::A));
+    }
+}
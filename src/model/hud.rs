@@ -1,10 +1,12 @@
 use super::*;
 
 #[turbo::serialize]
+#[allow(clippy::upper_case_acronyms)]
 pub struct HUD {
     notification_timer: u32,
 }
 impl HUD {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         HUD {
             notification_timer: 0,
@@ -13,7 +15,7 @@ impl HUD {
     // update is called once per frame within the [turbo::game] loop
     pub fn update(&mut self, player: &mut Player) {
         // Notifications timer
-        if player.notifications.len() > 0 {
+        if !player.notifications.is_empty() {
             self.notification_timer += 1;
             // Remove current notification if timer expires
             if self.notification_timer >= 120 - 1 {
@@ -23,8 +25,10 @@ impl HUD {
         }
     }
 
-    pub fn draw(&self, player: &Player) {
-        let (screen_w, _) = resolution();
+    // best is the top score on the high score table
+    // In co-op each player's HP and score share the top bar, with their buffs and weapon on their side of the screen
+    pub fn draw(&self, players: &[Player], best: u32, bindings: &Bindings) {
+        let (screen_w, screen_h) = resolution();
         // Drawing the HUD panel
         let hud_height = 16; // Height of the HUD panel
         rect!(
@@ -38,7 +42,7 @@ impl HUD {
         // Drawing borders for the HUD section
         rect!(
             x = 0,
-            y = hud_height as i32,
+            y = hud_height,
             w = screen_w,
             h = 1,
             // border = 1,
@@ -49,37 +53,175 @@ impl HUD {
         let hud_padding = 4; // Padding inside the HUD
         let text_color = 0xffffffff; // White text color
 
-        // Display Health
-        let health_text = format!("HP: {}", player.hp);
-        let health_text_x = 8;
+        match players {
+            [player] => {
+                // Display Health
+                let health_text = format!("HP: {}", player.hp);
+                let health_text_x = 8;
+                text!(
+                    &health_text,
+                    x = health_text_x,
+                    y = hud_padding,
+                    font = "large",
+                    color = text_color
+                );
+
+                // Display Score
+                let score_text = format!("SCORE: {:0>5}", player.score);
+                let score_text_x =
+                    screen_w as i32 - (score_text.chars().count() as i32 * 8) - hud_padding;
+                text!(
+                    &score_text,
+                    x = score_text_x,
+                    y = hud_padding,
+                    font = "large",
+                    color = text_color
+                );
+            }
+            // Display each player's Health and Score in their own color, the partner's on the right
+            _ => {
+                for player in players {
+                    let status_text = format!("P{} HP:{} {:0>5}", player.index + 1, player.hp, player.score);
+                    let status_text_x = match player.index {
+                        0 => hud_padding,
+                        _ => screen_w as i32 - (status_text.chars().count() as i32 * 5) - hud_padding,
+                    };
+                    text!(
+                        &status_text,
+                        x = status_text_x,
+                        y = hud_padding + 2,
+                        font = "medium",
+                        color = player.tint()
+                    );
+                }
+            }
+        }
+
+        // Display the best score, which the current run takes over once it beats it
+        let score: u32 = players.iter().map(|player| player.score).sum();
+        let best_text = format!("HI {:0>5}", best.max(score));
         text!(
-            &health_text,
-            x = health_text_x,
-            y = hud_padding,
-            font = "large",
-            color = text_color
+            &best_text,
+            x = (screen_w as i32 - best_text.chars().count() as i32 * 5) / 2,
+            y = hud_padding + 2,
+            font = "medium",
+            color = 0xaaaaaaff
         );
 
-        // Display Score
-        let score_text = format!("SCORE: {:0>5}", player.score);
-        let score_text_x =
-            screen_w as i32 - (score_text.chars().count() as i32 * 8) - hud_padding;
+        for player in players {
+            // Display active buffs below the HUD, each with an icon, stack count and a bar for the time remaining
+            for (i, buff) in player.buffs.iter().enumerate() {
+                let x = match player.index {
+                    0 => hud_padding + i as i32 * 24,
+                    _ => screen_w as i32 - hud_padding - 20 - i as i32 * 24,
+                };
+                let y = 36;
+                // blink the icon during the last 2 seconds
+                if buff.remaining > 120 || (buff.remaining / 8).is_multiple_of(2) {
+                    sprite!(buff.kind.sprite(), x = x, y = y);
+                }
+                if buff.stacks > 1 {
+                    let stacks_text = format!("x{}", buff.stacks);
+                    text!(
+                        &stacks_text,
+                        x = x + 10,
+                        y = y + 1,
+                        font = "medium",
+                        color = text_color
+                    );
+                }
+                let bar_w = 20 * buff.remaining / buff.kind.duration();
+                rect!(x = x, y = y + 10, w = 20, h = 2, color = 0x333333ff);
+                rect!(x = x, y = y + 10, w = bar_w, h = 2, color = 0x22aaaaff);
+            }
+
+            // Display the current weapon and its ammo in the bottom left corner, or the bottom right for the partner
+            let weapon = &player.weapons[player.weapon_index];
+            let ammo_text = match weapon.ammo {
+                Some(ammo) => ammo.to_string(),
+                None => "--".to_string(),
+            };
+            let mut weapon_text = format!("{} {}", weapon.name(), ammo_text);
+            // in co-op the weapon slot goes right after the weapon, since both corners are taken
+            if players.len() > 1 && player.weapons.len() > 1 {
+                weapon_text = format!("{} {}/{}", weapon_text, player.weapon_index + 1, player.weapons.len());
+            }
+            let weapon_text_x = match player.index {
+                0 => hud_padding,
+                _ => screen_w as i32 - (weapon_text.chars().count() as i32 * 5) - hud_padding,
+            };
+            text!(
+                &weapon_text,
+                x = weapon_text_x,
+                y = screen_h as i32 - 12,
+                font = "medium",
+                color = text_color
+            );
+        }
+        // Show the weapon slot in the bottom right corner when there's more than one to switch between
+        if let [player] = players {
+            if player.weapons.len() > 1 {
+                let slot_text = format!(
+                    "{}/{} {}/{}",
+                    bindings.button(Action::NextWeapon).name(),
+                    bindings.button(Action::PreviousWeapon).name(),
+                    player.weapon_index + 1,
+                    player.weapons.len()
+                );
+                let slot_text_x =
+                    screen_w as i32 - (slot_text.chars().count() as i32 * 5) - hud_padding;
+                text!(
+                    &slot_text,
+                    x = slot_text_x,
+                    y = screen_h as i32 - 12,
+                    font = "medium",
+                    color = text_color
+                );
+            }
+        }
+    }
+
+    // Health bar for a boss, below the buffs
+    // The bar fills up while the boss flies in, then drains as it takes damage
+    pub fn draw_boss(&self, enemy: &Enemy) {
+        let (screen_w, _) = resolution();
+        let Some(boss) = &enemy.boss else {
+            return;
+        };
+        let fill = if boss.entering {
+            let h = enemy.hitbox.h as f32;
+            ((enemy.hitbox.y + h) / (boss.definition.entrance_y + h)).clamp(0.0, 1.0)
+        } else {
+            enemy.hp as f32 / boss.max_hp as f32
+        };
+        let bar_w = screen_w - 8;
+        let name_text = enemy.enemy_type.to_uppercase();
         text!(
-            &score_text,
-            x = score_text_x,
-            y = hud_padding,
-            font = "large",
-            color = text_color
-        )
+            &name_text,
+            x = 4,
+            y = 52,
+            font = "medium",
+            color = 0xffffffff
+        );
+        let phase_text = format!("{}/{}", boss.phase + 1, boss.definition.phases.len());
+        text!(
+            &phase_text,
+            x = screen_w as i32 - 4 - phase_text.chars().count() as i32 * 5,
+            y = 52,
+            font = "medium",
+            color = 0xffcc44ff
+        );
+        rect!(x = 4, y = 60, w = bar_w, h = 4, color = 0x333333ff);
+        rect!(x = 4, y = 60, w = (bar_w as f32 * fill) as u32, h = 4, color = 0xe60000ff);
     }
 
     pub fn draw_notifications(&self, player: &Player) {
         let (screen_w, _) = resolution();
-        // Render notifications
-        for notif in player.notifications.iter() {
+        // Render the oldest notification
+        if let Some(notif) = player.notifications.first() {
             let len = notif.chars().count();
             let w = len * 5;
-            let x = (screen_w as usize / 2) - (w / 2); // center the text based on width
+            let x = (screen_w as usize / 2).saturating_sub(w / 2); // center the text based on width, or start at the left edge when it is wider than the screen
             rect!(
                 w = w as u32 + 4,
                 h = 10,
@@ -88,17 +230,16 @@ impl HUD {
                 color = 0x22aaaaff
             );
             text!(
-                &notif,
+                notif,
                 x = x as i32,
                 y = 24,
                 font = "medium",
                 color = 0xffffffff
             );
-            break;
         }
     }
 
-    pub fn draw_menu(&self, tick: u32,) {
+    pub fn draw_menu(&self, tick: u32, has_replay: bool, level: &str) {
         let (screen_w, screen_h) = resolution();
 
         text!(
@@ -116,6 +257,76 @@ impl HUD {
                 font = "medium"
             );
         }
+        // Selected level, changed with left and right
+        let level_text = format!("< {} >", level);
+        text!(
+            &level_text,
+            x = (screen_w as i32 - level_text.chars().count() as i32 * 5) / 2,
+            y = (screen_h as i32 / 2) - 2,
+            font = "medium",
+            color = 0xffcc44ff
+        );
+        text!(
+            "B for options",
+            x = (screen_w as i32 / 2) - 32,
+            y = (screen_h as i32 / 2) + 48,
+            font = "medium",
+            color = 0xaaaaaaff
+        );
+        text!(
+            "X for high scores",
+            x = (screen_w as i32 / 2) - 42,
+            y = (screen_h as i32 / 2) + 36,
+            font = "medium",
+            color = 0xaaaaaaff
+        );
+        // The last run can be watched back once there is one
+        if has_replay {
+            text!(
+                "SELECT or Y to watch last run",
+                x = (screen_w as i32 / 2) - 72,
+                y = (screen_h as i32 / 2) + 24,
+                font = "medium",
+                color = 0xaaaaaaff
+            );
+        }
+    }
+
+    // Shown while the attract mode demo is playing
+    pub fn draw_demo(&self, tick: u32) {
+        let (screen_w, screen_h) = resolution();
+        text!(
+            "DEMO",
+            x = screen_w as i32 - 24,
+            y = 22,
+            font = "medium",
+            color = 0xffffffff
+        );
+        // blink start message
+        if tick / 4 % 8 < 4 {
+            text!(
+                "Press START or A to play",
+                x = (screen_w as i32 / 2) - 60,
+                y = screen_h as i32 - 48,
+                font = "medium"
+            );
+        }
+    }
+
+    // Shown while a replay is playing back
+    pub fn draw_replay(&self, tick: u32) {
+        let (screen_w, _) = resolution();
+        // blinking recording dot
+        if tick / 4 % 8 < 4 {
+            circ!(x = screen_w as i32 - 112, y = 22, d = 5, color = 0xff3344ff);
+        }
+        text!(
+            "REPLAY  SELECT: EXIT",
+            x = screen_w as i32 - 104,
+            y = 22,
+            font = "medium",
+            color = 0xffffffff
+        );
     }
 
     pub fn draw_game_over(&self, tick: u32,) {
@@ -137,4 +348,266 @@ impl HUD {
             );
         }
     }
-}
\ No newline at end of file
+
+    // Initials entry for a new high score, with the letter being changed blinking
+    pub fn draw_name_entry(&self, entry: &NameEntry, score: u32, tick: u32) {
+        let (screen_w, screen_h) = resolution();
+        let center_y = screen_h as i32 / 2;
+        rect!(x = 16, y = center_y - 48, w = screen_w - 32, h = 96, color = 0x000000dd);
+        text!(
+            "NEW HIGH SCORE!",
+            x = (screen_w as i32 / 2) - 60,
+            y = center_y - 40,
+            font = "large",
+            color = 0xffcc44ff
+        );
+        let score_text = format!("{:0>5}", score);
+        text!(
+            &score_text,
+            x = (screen_w as i32 - score_text.chars().count() as i32 * 8) / 2,
+            y = center_y - 24,
+            font = "large"
+        );
+        let initials = entry.initials();
+        for (i, letter) in initials.chars().enumerate() {
+            let x = (screen_w as i32 / 2) - 20 + i as i32 * 16;
+            let selected = i == entry.cursor;
+            if !selected || tick / 4 % 8 < 6 {
+                let letter_text = letter.to_string();
+                text!(
+                    &letter_text,
+                    x = x,
+                    y = center_y - 4,
+                    font = "large",
+                    color = if selected { 0xffcc44ff } else { 0xffffffff }
+                );
+            }
+            // underline every slot, the selected one brighter
+            rect!(
+                x = x - 1,
+                y = center_y + 6,
+                w = 10,
+                h = 1,
+                color = if selected { 0xffcc44ff } else { 0x666666ff }
+            );
+        }
+        text!(
+            "UP/DOWN: LETTER  LEFT/RIGHT: MOVE",
+            x = (screen_w as i32 / 2) - 82,
+            y = center_y + 20,
+            font = "medium",
+            color = 0xaaaaaaff
+        );
+        text!(
+            "START: DONE",
+            x = (screen_w as i32 / 2) - 27,
+            y = center_y + 32,
+            font = "medium",
+            color = 0xaaaaaaff
+        );
+    }
+
+    // Table of the best scores, with the one just entered blinking
+    pub fn draw_high_scores(&self, scores: &HighScores, highlight: Option<usize>, tick: u32) {
+        let (screen_w, screen_h) = resolution();
+        text!(
+            "HIGH SCORES",
+            x = (screen_w as i32 / 2) - 44,
+            y = 96,
+            font = "large"
+        );
+        if scores.entries.is_empty() {
+            text!(
+                "NO SCORES YET",
+                x = (screen_w as i32 / 2) - 32,
+                y = 124,
+                font = "medium",
+                color = 0xaaaaaaff
+            );
+        }
+        for (i, entry) in scores.entries.iter().enumerate() {
+            if highlight == Some(i) && tick / 4 % 8 >= 6 {
+                continue;
+            }
+            let row_text = format!("{:>2}. {}  {:0>5}  {}", i + 1, entry.initials, entry.score, entry.level);
+            text!(
+                &row_text,
+                x = 40,
+                y = 124 + i as i32 * 14,
+                font = "medium",
+                color = if highlight == Some(i) { 0xffcc44ff } else { 0xffffffff }
+            );
+        }
+        // blink continue message
+        if tick / 4 % 8 < 4 {
+            text!(
+                "PRESS START",
+                x = (screen_w as i32 / 2) - 24,
+                y = screen_h as i32 - 96,
+                font = "medium"
+            );
+        }
+    }
+
+    // Pause menu over a dimmed game
+    pub fn draw_pause_menu(&self, menu: &PauseMenu) {
+        let (screen_w, screen_h) = resolution();
+        rect!(x = 0, y = 0, w = screen_w, h = screen_h, color = 0x000000aa);
+        text!(
+            "PAUSED",
+            x = (screen_w as i32 / 2) - 24,
+            y = (screen_h as i32 / 2) - 44,
+            font = "large"
+        );
+        for (i, option) in PauseOption::ALL.iter().enumerate() {
+            let bounds = PauseMenu::bounds(i, (screen_w, screen_h));
+            let selected = i == menu.selected;
+            if selected {
+                rect!(
+                    x = bounds.x as i32,
+                    y = bounds.y as i32,
+                    w = bounds.w,
+                    h = bounds.h,
+                    color = 0x22aaaaff
+                );
+            }
+            let label = option.label();
+            text!(
+                label,
+                x = bounds.x as i32 + (bounds.w as i32 - label.chars().count() as i32 * 5) / 2,
+                y = bounds.y as i32 + 4,
+                font = "medium",
+                color = 0xffffffff
+            );
+        }
+    }
+
+    // Covers the screen while the scenes change
+    pub fn draw_transition(&self, transition: &Transition) {
+        let (screen_w, screen_h) = resolution();
+        let cover = transition.cover();
+        match transition.effect {
+            TransitionEffect::Fade => {
+                rect!(
+                    x = 0,
+                    y = 0,
+                    w = screen_w,
+                    h = screen_h,
+                    color = (cover * 255.0) as u32
+                );
+            }
+            // the curtain comes in from the left while closing, and leaves to the right while opening
+            TransitionEffect::Wipe => {
+                let w = (screen_w as f32 * cover) as u32;
+                let x = if transition.closing() { 0 } else { screen_w - w };
+                rect!(x = x, y = 0, w = w, h = screen_h, color = 0x000000ff);
+            }
+            // a ring with a border thick enough to reach the corners, leaving a hole that shrinks to nothing
+            TransitionEffect::Iris => {
+                let border = ((screen_w * screen_w + screen_h * screen_h) as f32).sqrt() as u32 / 2 + 1;
+                let hole = (border as f32 * 2.0 * (1.0 - cover)) as u32;
+                let d = hole + border * 2;
+                circ!(
+                    x = screen_w as i32 / 2 - d as i32 / 2,
+                    y = screen_h as i32 / 2 - d as i32 / 2,
+                    d = d,
+                    color = 0x00000000,
+                    border_size = border,
+                    border_color = 0x000000ff
+                );
+            }
+        }
+    }
+
+    // Options screen, covering whatever it was opened from
+    pub fn draw_options(&self, menu: &OptionsMenu, settings: &Settings) {
+        let (screen_w, screen_h) = resolution();
+        rect!(x = 0, y = 0, w = screen_w, h = screen_h, color = 0x000000ee);
+        text!(
+            "OPTIONS",
+            x = (screen_w as i32 / 2) - 28,
+            y = (screen_h as i32 / 2) - 80,
+            font = "large"
+        );
+        for (i, item) in OptionsItem::ALL.iter().enumerate() {
+            let bounds = OptionsMenu::bounds(i, (screen_w, screen_h));
+            let (x, y) = (bounds.x as i32, bounds.y as i32);
+            if i == menu.selected {
+                rect!(x = x, y = y, w = bounds.w, h = bounds.h, color = 0x22aaaaff);
+            }
+            text!(item.label(), x = x + 4, y = y + 4, font = "medium", color = 0xffffffff);
+            // levels are drawn as a bar of segments on the right, other values as text
+            if let Some(level) = item.level(settings) {
+                for segment in 0..MAX_LEVEL {
+                    let color = if segment < level { 0xffcc44ff } else { 0x444444ff };
+                    rect!(x = x + 126 + segment as i32 * 6, y = y + 4, w = 4, h = 6, color = color);
+                }
+            } else {
+                let value = item.value(settings);
+                text!(
+                    value,
+                    x = x + bounds.w as i32 - 4 - value.chars().count() as i32 * 5,
+                    y = y + 4,
+                    font = "medium",
+                    color = 0xffcc44ff
+                );
+            }
+        }
+        text!(
+            "LEFT/RIGHT: CHANGE  B: BACK",
+            x = (screen_w as i32 / 2) - 67,
+            y = (screen_h as i32 / 2) + 72,
+            font = "medium",
+            color = 0xaaaaaaff
+        );
+    }
+
+    // Controls screen, over the options, listing the button each action is on
+    pub fn draw_controls(&self, menu: &ControlsMenu, bindings: &Bindings) {
+        let (screen_w, screen_h) = resolution();
+        rect!(x = 0, y = 0, w = screen_w, h = screen_h, color = 0x000000ee);
+        text!(
+            "CONTROLS",
+            x = (screen_w as i32 / 2) - 32,
+            y = (screen_h as i32 / 2) - 132,
+            font = "large"
+        );
+        let gamepad = (menu.gamepad + 1).to_string();
+        for i in 0..=CONTROLS_BACK_ROW {
+            let bounds = ControlsMenu::bounds(i, (screen_w, screen_h));
+            let (x, y) = (bounds.x as i32, bounds.y as i32);
+            if i == menu.selected {
+                rect!(x = x, y = y, w = bounds.w, h = bounds.h, color = 0x22aaaaff);
+            }
+            let (label, value) = match i {
+                CONTROLS_GAMEPAD_ROW => ("GAMEPAD", gamepad.as_str()),
+                CONTROLS_RESET_ROW => ("RESET TO DEFAULTS", ""),
+                CONTROLS_BACK_ROW => ("BACK", ""),
+                _ => {
+                    let action = Action::ALL[i];
+                    // the selected action's button blinks while waiting for a new one
+                    match menu.waiting {
+                        Some(frames) if i == menu.selected && frames / 15 % 2 == 0 => (action.name(), ""),
+                        Some(_) if i == menu.selected => (action.name(), "PRESS A BUTTON"),
+                        _ => (action.name(), bindings.button(action).name()),
+                    }
+                }
+            };
+            text!(label, x = x + 4, y = y + 4, font = "medium", color = 0xffffffff);
+            text!(
+                value,
+                x = x + bounds.w as i32 - 4 - value.chars().count() as i32 * 5,
+                y = y + 4,
+                font = "medium",
+                color = 0xffcc44ff
+            );
+        }
+        text!(
+            "A: REBIND  B: BACK",
+            x = (screen_w as i32 / 2) - 45,
+            y = (screen_h as i32 / 2) + 104,
+            font = "medium",
+            color = 0xaaaaaaff
+        );
+    }
+}
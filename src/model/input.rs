@@ -0,0 +1,104 @@
+// Buttons on the gamepad used by the game
+#[turbo::serialize]
+#[derive(PartialEq, Copy)]
+pub enum Button {
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    B,
+    X,
+    Y,
+    Start,
+    Select,
+}
+
+impl Button {
+    pub const ALL: [Button; 10] = [
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+        Button::A,
+        Button::B,
+        Button::X,
+        Button::Y,
+        Button::Start,
+        Button::Select,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Button::Up => "UP",
+            Button::Down => "DOWN",
+            Button::Left => "LEFT",
+            Button::Right => "RIGHT",
+            Button::A => "A",
+            Button::B => "B",
+            Button::X => "X",
+            Button::Y => "Y",
+            Button::Start => "START",
+            Button::Select => "SELECT",
+        }
+    }
+}
+
+// Snapshot of the player's input for a single frame
+// Gameplay reads input from these instead of the gamepad directly, so a run can be recorded and played back
+#[turbo::serialize]
+#[derive(PartialEq, Default)]
+pub struct InputFrame {
+    buttons: u16, // one bit per Button
+    pub pointer: Option<(i32, i32)>, // screen position of the pointer while it's pressed
+}
+
+impl InputFrame {
+    pub fn set(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.buttons |= 1 << button as u16;
+        } else {
+            self.buttons &= !(1 << button as u16);
+        }
+    }
+
+    pub fn pressed(&self, button: Button) -> bool {
+        self.buttons & (1 << button as u16) != 0
+    }
+}
+
+// Input for the current frame along with the previous one, used to tell when a button was just pressed
+#[turbo::serialize]
+#[derive(Default)]
+pub struct Input {
+    pub frame: InputFrame,
+    previous: InputFrame,
+}
+
+impl Input {
+    // Moves on to the next frame of input
+    pub fn update(&mut self, frame: InputFrame) {
+        self.previous = std::mem::replace(&mut self.frame, frame);
+    }
+
+    pub fn pressed(&self, button: Button) -> bool {
+        self.frame.pressed(button)
+    }
+
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.frame.pressed(button) && !self.previous.pressed(button)
+    }
+
+    // Screen position of the pointer while it's pressed
+    pub fn pointer(&self) -> Option<(i32, i32)> {
+        self.frame.pointer
+    }
+
+    // Screen position of the pointer on the frame it's first pressed
+    pub fn pointer_just_pressed(&self) -> Option<(i32, i32)> {
+        match self.previous.pointer {
+            Some(_) => None,
+            None => self.frame.pointer,
+        }
+    }
+}
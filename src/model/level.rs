@@ -0,0 +1,292 @@
+use super::*;
+
+// Built-in level scripts, embedded at build time like the enemy definitions
+// The first one is played by default
+const LEVELS_JSON: [&str; 2] = [
+    include_str!("../../data/levels/endless.json"),
+    include_str!("../../data/levels/first_contact.json"),
+];
+
+// A level script, a timeline of steps run one after another
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub name: String, // shown on the menu
+    pub steps: Vec<LevelStep>,
+}
+
+#[turbo::serialize]
+pub enum LevelStep {
+    // Spawns a group of enemies, one every interval frames, in the given formation
+    Spawn {
+        enemy: String, // name from data/enemies.json
+        #[serde(default = "default_count")]
+        count: u32,
+        #[serde(default)]
+        formation: Formation,
+        #[serde(default)]
+        interval: u32,
+    },
+    // Waits for the given number of frames
+    Wait { frames: u32 },
+    // Waits until every enemy on screen is gone
+    WaitUntilCleared,
+    // Spawns a boss in the middle of the screen, then waits until it's destroyed
+    Boss { enemy: String }, // name from data/enemies.json, which must have a boss section
+    // Spawns random enemies forever, more and more often, picked using their spawn weights
+    Endless {
+        initial_interval: u32, // frames between spawns at the start
+        minimum_interval: u32, // frames between spawns once fully sped up
+        speed_up: u32, // frames it takes for the interval to shrink by 1
+        max_enemies: u32, // no spawning while there are this many enemies on screen
+    },
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+// Where the enemies of a Spawn step appear
+// x is a fraction of the screen width, 0.0 for the left edge and 1.0 for the right
+#[turbo::serialize]
+#[derive(Default)]
+pub enum Formation {
+    #[default]
+    Random, // each enemy at a random x
+    Column { x: f32 }, // all at the same x, so with an interval they trail one another
+    Row { x: f32, spacing: f32 }, // side by side, centered on x
+    V { x: f32, spacing: f32 }, // a V pointing down, centered on x
+}
+
+// Problems found while loading level scripts
+#[derive(Debug)]
+pub enum LevelError {
+    Parse(usize, String), // index of the built-in level and the parse error
+    Empty(String), // level name
+    UnknownEnemy(String, String), // level name and the enemy name that isn't defined
+    InvalidStep(String, usize), // level name and the index of the step
+    NotABoss(String, String), // level name and the enemy name used in a Boss step
+}
+
+impl std::fmt::Display for LevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LevelError::Parse(index, err) => write!(f, "couldn't parse level #{}: {}", index, err),
+            LevelError::Empty(name) => write!(f, "level \"{}\" has no steps", name),
+            LevelError::UnknownEnemy(name, enemy) => write!(f, "level \"{}\" spawns \"{}\", which isn't in data/enemies.json", name, enemy),
+            LevelError::InvalidStep(name, index) => write!(f, "level \"{}\" step #{} has a count, interval or formation out of range", name, index),
+            LevelError::NotABoss(name, enemy) => write!(f, "level \"{}\" has a Boss step for \"{}\", which has no boss section", name, enemy),
+        }
+    }
+}
+
+impl Level {
+    // Loads and validates the built-in level scripts against the enemy definitions
+    pub fn load(definitions: &[EnemyDefinition]) -> Result<Vec<Level>, LevelError> {
+        let mut levels = vec![];
+        for (i, json) in LEVELS_JSON.iter().enumerate() {
+            let level: Level = serde_json::from_str(json).map_err(|err| LevelError::Parse(i, err.to_string()))?;
+            level.validate(definitions)?;
+            levels.push(level);
+        }
+        Ok(levels)
+    }
+
+    fn validate(&self, definitions: &[EnemyDefinition]) -> Result<(), LevelError> {
+        if self.steps.is_empty() {
+            return Err(LevelError::Empty(self.name.clone()));
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            let valid = match step {
+                LevelStep::Spawn { enemy, count, formation, .. } => {
+                    if !definitions.iter().any(|definition| definition.name == *enemy) {
+                        return Err(LevelError::UnknownEnemy(self.name.clone(), enemy.clone()));
+                    }
+                    let x = match formation {
+                        Formation::Random => 0.0,
+                        Formation::Column { x } | Formation::Row { x, .. } | Formation::V { x, .. } => *x,
+                    };
+                    *count > 0 && (0.0..=1.0).contains(&x)
+                }
+                LevelStep::Endless { initial_interval, minimum_interval, speed_up, .. } => {
+                    *minimum_interval > 0 && initial_interval >= minimum_interval && *speed_up > 0
+                }
+                LevelStep::Boss { enemy } => {
+                    let Some(definition) = definitions.iter().find(|definition| definition.name == *enemy) else {
+                        return Err(LevelError::UnknownEnemy(self.name.clone(), enemy.clone()));
+                    };
+                    if definition.boss.is_none() {
+                        return Err(LevelError::NotABoss(self.name.clone(), enemy.clone()));
+                    }
+                    true
+                }
+                LevelStep::Wait { .. } | LevelStep::WaitUntilCleared => true,
+            };
+            if !valid {
+                return Err(LevelError::InvalidStep(self.name.clone(), i));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Runs a level script, spawning its enemies
+#[turbo::serialize]
+pub struct LevelRunner {
+    pub level: Level,
+    pub step: usize, // index of the current step
+    timer: u32, // frames spent on the current step
+    spawned: u32, // enemies spawned so far by the current Spawn step
+}
+
+impl LevelRunner {
+    pub fn new(level: Level) -> Self {
+        LevelRunner {
+            level,
+            step: 0,
+            timer: 0,
+            spawned: 0,
+        }
+    }
+
+    // Whether every step of the level has been run
+    pub fn finished(&self) -> bool {
+        self.step >= self.level.steps.len()
+    }
+
+    // update is called once per frame within the [turbo::game] loop
+    pub fn update(&mut self, enemies: &mut Vec<Enemy>, definitions: &[EnemyDefinition], rng: &mut GameRng, platform: &dyn Platform) {
+        let Some(step) = self.level.steps.get(self.step) else {
+            return;
+        };
+        let done = match step {
+            LevelStep::Spawn { enemy, count, formation, interval } => {
+                if *interval == 0 || self.timer.is_multiple_of(*interval) {
+                    // Spawn the whole group at once without an interval
+                    let batch = if *interval == 0 { *count - self.spawned } else { 1 };
+                    for _ in 0..batch {
+                        let definition = definitions
+                            .iter()
+                            .find(|definition| definition.name == *enemy)
+                            .expect("level enemies are validated when loading");
+                        let mut spawned = Enemy::new(definition, rng, platform);
+                        place(&mut spawned, formation, self.spawned, *count, platform);
+                        enemies.push(spawned);
+                        self.spawned += 1;
+                    }
+                }
+                self.spawned >= *count
+            }
+            LevelStep::Wait { frames } => self.timer + 1 >= *frames,
+            LevelStep::WaitUntilCleared => enemies.is_empty(),
+            LevelStep::Boss { enemy } => {
+                if self.timer == 0 {
+                    let definition = definitions
+                        .iter()
+                        .find(|definition| definition.name == *enemy)
+                        .expect("level enemies are validated when loading");
+                    let mut boss = Enemy::new(definition, rng, platform);
+                    place(&mut boss, &Formation::Column { x: 0.5 }, 0, 1, platform);
+                    enemies.push(boss);
+                }
+                !enemies.iter().any(|enemy| enemy.boss.is_some())
+            }
+            LevelStep::Endless { initial_interval, minimum_interval, speed_up, max_enemies } => {
+                // Calculate current spawn interval based on time elapsed
+                let spawn_rate = std::cmp::max(
+                    *minimum_interval,
+                    initial_interval.saturating_sub(self.timer / speed_up),
+                );
+                // Spawn a new enemy if the timer is a multiple of the spawn rate
+                if self.timer.is_multiple_of(spawn_rate) && (enemies.len() as u32) < *max_enemies {
+                    let definition = EnemyDefinition::pick(definitions, rng);
+                    enemies.push(Enemy::new(definition, rng, platform));
+                }
+                false
+            }
+        };
+        // Move on to the next step
+        if done {
+            self.step += 1;
+            self.timer = 0;
+            self.spawned = 0;
+        } else {
+            self.timer += 1;
+        }
+    }
+}
+
+// Positions the index-th enemy of a group of count according to the formation
+fn place(enemy: &mut Enemy, formation: &Formation, index: u32, count: u32, platform: &dyn Platform) {
+    let (screen_w, _) = platform.resolution();
+    // offset from the middle of the group, in number of enemies
+    let offset = index as f32 - (count - 1) as f32 / 2.0;
+    let half_w = enemy.hitbox.w as f32 / 2.0;
+    match *formation {
+        Formation::Random => {}
+        Formation::Column { x } => {
+            enemy.hitbox.x = x * screen_w as f32 - half_w;
+        }
+        Formation::Row { x, spacing } => {
+            enemy.hitbox.x = x * screen_w as f32 + offset * spacing - half_w;
+        }
+        Formation::V { x, spacing } => {
+            enemy.hitbox.x = x * screen_w as f32 + offset * spacing - half_w;
+            // the wings trail behind the leader
+            enemy.hitbox.y -= offset.abs() * spacing / 2.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(steps: &str) -> Level {
+        serde_json::from_str(&format!(r#"{{ "name": "TEST", "steps": {} }}"#, steps)).unwrap()
+    }
+
+    #[test]
+    fn built_in_levels_are_valid() {
+        let definitions = EnemyDefinition::load().unwrap();
+        let levels = Level::load(&definitions).unwrap();
+        assert_eq!(levels.len(), LEVELS_JSON.len());
+        // the scripted level ends on its boss
+        assert!(levels.iter().any(|level| matches!(level.steps.last(), Some(LevelStep::Boss { .. }))));
+    }
+
+    #[test]
+    fn boss_steps_need_a_boss() {
+        let definitions = EnemyDefinition::load().unwrap();
+        assert!(level(r#"[{ "Boss": { "enemy": "dreadnought" } }]"#).validate(&definitions).is_ok());
+        assert!(matches!(
+            level(r#"[{ "Boss": { "enemy": "tank" } }]"#).validate(&definitions),
+            Err(LevelError::NotABoss(..))
+        ));
+        assert!(matches!(
+            level(r#"[{ "Boss": { "enemy": "mothership" } }]"#).validate(&definitions),
+            Err(LevelError::UnknownEnemy(..))
+        ));
+    }
+
+    #[test]
+    fn boss_step_waits_for_the_boss() {
+        let definitions = EnemyDefinition::load().unwrap();
+        let platform = MockPlatform::new(1, vec![]);
+        let mut rng = GameRng::new(1);
+        let mut runner = LevelRunner::new(level(r#"[{ "Spawn": { "enemy": "tank" } }, { "Boss": { "enemy": "dreadnought" } }]"#));
+        let mut enemies = vec![];
+        runner.update(&mut enemies, &definitions, &mut rng, &platform);
+        assert_eq!(runner.step, 1);
+        // the boss comes in once, and the level carries on around other enemies until it's gone
+        for _ in 0..10 {
+            runner.update(&mut enemies, &definitions, &mut rng, &platform);
+        }
+        assert_eq!(enemies.iter().filter(|enemy| enemy.boss.is_some()).count(), 1);
+        assert!(!runner.finished());
+        enemies.retain(|enemy| enemy.boss.is_none());
+        runner.update(&mut enemies, &definitions, &mut rng, &platform);
+        assert!(runner.finished());
+        assert_eq!(enemies.len(), 1);
+    }
+}
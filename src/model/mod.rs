@@ -1,14 +1,74 @@
 use super::*;
 
+pub mod action;
+pub use action::*;
+
+pub mod autopilot;
+pub use autopilot::*;
+
+pub mod boss;
+pub use boss::*;
+
+pub mod buff;
+pub use buff::*;
+
+pub mod collision;
+pub use collision::*;
+
+pub mod controls_menu;
+pub use controls_menu::*;
+
 pub mod enemy;
 pub use enemy::*;
 
+pub mod enemy_definition;
+pub use enemy_definition::*;
+
+pub mod grid;
+pub use grid::*;
+
+pub mod high_score;
+pub use high_score::*;
+
 pub mod hud;
 pub use hud::*;
 
+pub mod input;
+pub use input::*;
+
+pub mod level;
+pub use level::*;
+
+pub mod rng;
+pub use rng::*;
+
+pub mod save;
+pub use save::*;
+
+pub mod scene;
+pub use scene::*;
+
+pub mod settings;
+pub use settings::*;
+
+pub mod stats;
+pub use stats::*;
+
 pub mod utils;
 pub use utils::*;
 
+pub mod pause_menu;
+pub use pause_menu::*;
+
+pub mod options_menu;
+pub use options_menu::*;
+
+pub mod pattern;
+pub use pattern::*;
+
+pub mod platform;
+pub use platform::*;
+
 pub mod player;
 pub use player::*;
 
@@ -16,4 +76,10 @@ pub mod power_up;
 pub use power_up::*;
 
 pub mod projectile;
-pub use projectile::*;
\ No newline at end of file
+pub use projectile::*;
+
+pub mod replay;
+pub use replay::*;
+
+pub mod weapon;
+pub use weapon::*;
\ No newline at end of file
@@ -0,0 +1,206 @@
+use super::*;
+
+// Rows on the options menu, from top to bottom
+#[turbo::serialize]
+#[derive(PartialEq, Copy)]
+pub enum OptionsItem {
+    MusicVolume,
+    SfxVolume,
+    Shake,
+    HitFlash,
+    AutoFire,
+    Difficulty,
+    Controls, // opens the controls screen
+    Back, // closes the menu
+}
+
+impl OptionsItem {
+    pub const ALL: [OptionsItem; 8] = [
+        OptionsItem::MusicVolume,
+        OptionsItem::SfxVolume,
+        OptionsItem::Shake,
+        OptionsItem::HitFlash,
+        OptionsItem::AutoFire,
+        OptionsItem::Difficulty,
+        OptionsItem::Controls,
+        OptionsItem::Back,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OptionsItem::MusicVolume => "MUSIC",
+            OptionsItem::SfxVolume => "SOUND FX",
+            OptionsItem::Shake => "SCREEN SHAKE",
+            OptionsItem::HitFlash => "HIT FLASH",
+            OptionsItem::AutoFire => "AUTO-FIRE",
+            OptionsItem::Difficulty => "DIFFICULTY (NEXT RUN)", // runs keep the difficulty they started with
+            OptionsItem::Controls => "CONTROLS",
+            OptionsItem::Back => "BACK",
+        }
+    }
+
+    // Current level for the rows that go from 0 to MAX_LEVEL, drawn as a bar
+    pub fn level(&self, settings: &Settings) -> Option<u32> {
+        match self {
+            OptionsItem::MusicVolume => Some(settings.music_volume),
+            OptionsItem::SfxVolume => Some(settings.sfx_volume),
+            OptionsItem::Shake => Some(settings.shake),
+            OptionsItem::HitFlash => Some(settings.hit_flash),
+            _ => None,
+        }
+    }
+
+    // Current value for the other rows, drawn as text
+    pub fn value(&self, settings: &Settings) -> &'static str {
+        match self {
+            OptionsItem::AutoFire if settings.auto_fire => "ON",
+            OptionsItem::AutoFire => "OFF",
+            OptionsItem::Difficulty => settings.difficulty.name(),
+            _ => "",
+        }
+    }
+
+    // Moves the setting up or down a step, levels stop at their ends and the others wrap around
+    fn change(&self, settings: &mut Settings, direction: i32) {
+        let step = |level: &mut u32| *level = (*level as i32 + direction).clamp(0, MAX_LEVEL as i32) as u32;
+        match self {
+            OptionsItem::MusicVolume => step(&mut settings.music_volume),
+            OptionsItem::SfxVolume => step(&mut settings.sfx_volume),
+            OptionsItem::Shake => step(&mut settings.shake),
+            OptionsItem::HitFlash => step(&mut settings.hit_flash),
+            OptionsItem::AutoFire => settings.auto_fire = !settings.auto_fire,
+            OptionsItem::Difficulty => {
+                let count = Difficulty::ALL.len() as i32;
+                let index = Difficulty::ALL.iter().position(|&difficulty| difficulty == settings.difficulty).unwrap_or(1);
+                settings.difficulty = Difficulty::ALL[(index as i32 + direction).rem_euclid(count) as usize];
+            }
+            OptionsItem::Controls | OptionsItem::Back => {}
+        }
+    }
+}
+
+// Options screen, opened from the main menu or the pause menu
+#[turbo::serialize]
+pub struct OptionsMenu {
+    pub selected: usize, // index into OptionsItem::ALL
+}
+
+impl OptionsMenu {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        OptionsMenu { selected: 0 }
+    }
+
+    // Up and down move the selection, left and right change the selected setting
+    // Tapping the left or right half of a row changes it down or up
+    // A or a tap on CONTROLS opens the controls screen, B, START, or picking BACK closes the menu
+    // Returns Back once the menu is closed, or Controls when the controls screen should be opened
+    pub fn update(&mut self, input: &Input, settings: &mut Settings, platform: &dyn Platform) -> Option<OptionsItem> {
+        let count = OptionsItem::ALL.len();
+        if input.just_pressed(Button::B) || input.just_pressed(Button::Start) {
+            return Some(OptionsItem::Back);
+        }
+        if input.just_pressed(Button::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.just_pressed(Button::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+        let item = OptionsItem::ALL[self.selected];
+        if input.just_pressed(Button::Left) {
+            item.change(settings, -1);
+        }
+        if input.just_pressed(Button::Right) {
+            item.change(settings, 1);
+        }
+        if input.just_pressed(Button::A) {
+            match item {
+                OptionsItem::Controls | OptionsItem::Back => return Some(item),
+                _ => item.change(settings, 1),
+            }
+        }
+        if let Some((px, py)) = input.pointer_just_pressed() {
+            let (px, py) = (px as f32, py as f32);
+            let screen = platform.resolution();
+            let tapped = (0..count).find(|&index| {
+                let bounds = Self::bounds(index, screen);
+                px >= bounds.x && px < bounds.x + bounds.w as f32 && py >= bounds.y && py < bounds.y + bounds.h as f32
+            });
+            if let Some(index) = tapped {
+                self.selected = index;
+                let bounds = Self::bounds(index, screen);
+                match OptionsItem::ALL[index] {
+                    item @ (OptionsItem::Controls | OptionsItem::Back) => return Some(item),
+                    item if px < bounds.x + bounds.w as f32 / 2.0 => item.change(settings, -1),
+                    item => item.change(settings, 1),
+                }
+            }
+        }
+        None
+    }
+
+    // Area of the screen a row is drawn in, and can be tapped in
+    pub fn bounds(index: usize, screen: (u32, u32)) -> Hitbox {
+        let (screen_w, screen_h) = screen;
+        Hitbox {
+            x: (screen_w / 2 - 96) as f32,
+            y: (screen_h / 2 - 64 + index as u32 * 16) as f32,
+            w: 192,
+            h: 14,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Presses a button for a frame, then lets go of it, returning what the menu returned on the press
+    fn tap(menu: &mut OptionsMenu, input: &mut Input, button: Button, settings: &mut Settings) -> Option<OptionsItem> {
+        let platform = MockPlatform::new(1, vec![]);
+        let mut frame = InputFrame::default();
+        frame.set(button, true);
+        input.update(frame);
+        let result = menu.update(input, settings, &platform);
+        input.update(InputFrame::default());
+        menu.update(input, settings, &platform);
+        result
+    }
+
+    #[test]
+    fn left_and_right_change_the_selected_setting() {
+        let mut menu = OptionsMenu::new();
+        let mut input = Input::default();
+        let mut settings = Settings::default();
+        assert_eq!(OptionsItem::ALL[menu.selected], OptionsItem::MusicVolume);
+        tap(&mut menu, &mut input, Button::Left, &mut settings);
+        tap(&mut menu, &mut input, Button::Left, &mut settings);
+        assert_eq!(settings.music_volume, MAX_LEVEL - 2);
+        assert_eq!(settings.sfx_volume, MAX_LEVEL);
+        // levels stop at their ends
+        for _ in 0..MAX_LEVEL + 5 {
+            tap(&mut menu, &mut input, Button::Right, &mut settings);
+        }
+        assert_eq!(settings.music_volume, MAX_LEVEL);
+        // the difficulty wraps around
+        while OptionsItem::ALL[menu.selected] != OptionsItem::Difficulty {
+            tap(&mut menu, &mut input, Button::Down, &mut settings);
+        }
+        tap(&mut menu, &mut input, Button::Right, &mut settings);
+        assert_eq!(settings.difficulty, Difficulty::Hard);
+        tap(&mut menu, &mut input, Button::Right, &mut settings);
+        assert_eq!(settings.difficulty, Difficulty::Easy);
+        assert_eq!(tap(&mut menu, &mut input, Button::B, &mut settings), Some(OptionsItem::Back));
+    }
+
+    #[test]
+    fn music_and_sound_effects_have_separate_volumes() {
+        let settings = Settings {
+            music_volume: 2,
+            sfx_volume: 5,
+            ..Settings::default()
+        };
+        assert_eq!(settings.volume("music_title"), 0.2);
+        assert_eq!(settings.volume("projectile_hit"), 0.5);
+    }
+}
@@ -0,0 +1,196 @@
+use super::*;
+
+// A volley of enemy bullets, as defined in data/enemies.json
+// Patterns are fired every few frames by the enemy or boss phase using them, and a list of them is fired together,
+// so e.g. a Ring with a Spread on top can be built without new code
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub struct BulletPattern {
+    pub shape: PatternShape,
+    #[serde(default = "default_count")]
+    pub count: u32, // number of bullets in a volley
+    #[serde(default)]
+    pub spacing: f32, // degrees between bullets for Spread and Wave, pixels between bullets for AimedBurst
+    #[serde(default)]
+    pub rotation: f32, // degrees the pattern turns every volley, for a Wave how fast it sweeps
+    #[serde(default)]
+    pub amplitude: f32, // degrees a Wave sweeps to either side of its aim
+    pub speed: f32,
+    #[serde(default)]
+    pub aim: Aim,
+}
+
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub enum PatternShape {
+    Spread, // fan of bullets centered on the aim
+    Ring, // bullets evenly spaced around a full circle, offset by half a gap every other volley
+    Spiral, // bullets evenly spaced around a full circle, set spinning by the rotation
+    AimedBurst, // a line of bullets one after another along the aim
+    Wave, // fan of bullets whose aim sweeps back and forth
+}
+
+// Where the middle of a pattern points
+#[turbo::serialize]
+#[derive(Default)]
+pub enum Aim {
+    #[default]
+    Player, // at the center of the nearest player's ship
+    Down, // straight down the screen
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+impl BulletPattern {
+    // A single bullet at the nearest player, the shot of the TargetPlayer strategy
+    pub fn aimed(speed: f32) -> Self {
+        BulletPattern {
+            shape: PatternShape::Spread,
+            count: 1,
+            spacing: 0.0,
+            rotation: 0.0,
+            amplitude: 0.0,
+            speed,
+            aim: Aim::Player,
+        }
+    }
+
+    // Whether the pattern fires anything, with a speed the bullets can travel at
+    pub fn is_valid(&self) -> bool {
+        self.count > 0
+            && self.speed.is_finite()
+            && self.speed > 0.0
+            && self.spacing.is_finite()
+            && self.rotation.is_finite()
+            && self.amplitude.is_finite()
+    }
+
+    // Angle of every bullet in the given volley, in degrees, from the angle the pattern is aimed at
+    pub fn angles(&self, aim: f32, volley: u32) -> Vec<f32> {
+        let count = self.count as f32;
+        let turn = self.rotation * volley as f32;
+        match self.shape {
+            PatternShape::Spread => (0..self.count)
+                .map(|i| aim + turn + (i as f32 - (count - 1.0) / 2.0) * self.spacing)
+                .collect(),
+            PatternShape::Ring => {
+                let step = 360.0 / count;
+                let offset = if volley.is_multiple_of(2) { 0.0 } else { step / 2.0 };
+                (0..self.count).map(|i| aim + turn + offset + step * i as f32).collect()
+            }
+            PatternShape::Spiral => {
+                let step = 360.0 / count;
+                (0..self.count).map(|i| aim + turn + step * i as f32).collect()
+            }
+            PatternShape::AimedBurst => vec![aim + turn; self.count as usize],
+            PatternShape::Wave => {
+                let sweep = self.amplitude * (self.rotation * volley as f32).to_radians().sin();
+                (0..self.count)
+                    .map(|i| aim + sweep + (i as f32 - (count - 1.0) / 2.0) * self.spacing)
+                    .collect()
+            }
+        }
+    }
+
+    // Fires a volley centered on origin, returning the new enemy projectiles
+    // volley counts the volleys fired so far, which is what turns spirals and sweeps waves
+    pub fn fire(&self, origin: (f32, f32), players: &[Player], volley: u32, rng: &mut GameRng, platform: &mut dyn Platform) -> Vec<Projectile> {
+        let (x, y) = origin;
+        let aim = match self.aim {
+            Aim::Player => {
+                let player = nearest_player(players, x, y);
+                let target_x = player.hitbox.x + player.hitbox.w as f32 / 2.0;
+                let target_y = player.hitbox.y + player.hitbox.h as f32 / 2.0;
+                (target_y - y).atan2(target_x - x).to_degrees()
+            }
+            Aim::Down => 90.0,
+        };
+        self.angles(aim, volley)
+            .into_iter()
+            .enumerate()
+            .map(|(i, angle)| {
+                let mut projectile = Projectile::new(x, y, self.speed, angle, ProjectileType::Basic, ProjectileOwner::Enemy, rng, platform);
+                // bullets of a burst start one behind the other, so they arrive one after another
+                let behind = match self.shape {
+                    PatternShape::AimedBurst => i as f32 * self.spacing,
+                    _ => 0.0,
+                };
+                let radian_angle = angle.to_radians();
+                projectile.hitbox.x -= projectile.hitbox.w as f32 / 2.0 + behind * radian_angle.cos();
+                projectile.hitbox.y -= projectile.hitbox.h as f32 / 2.0 + behind * radian_angle.sin();
+                projectile
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(shape: PatternShape, count: u32, spacing: f32, rotation: f32, amplitude: f32) -> BulletPattern {
+        BulletPattern { shape, count, spacing, rotation, amplitude, speed: 2.0, aim: Aim::Down }
+    }
+
+    fn assert_angles(angles: Vec<f32>, expected: &[f32]) {
+        assert_eq!(angles.len(), expected.len(), "{:?}", angles);
+        assert!(angles.iter().zip(expected).all(|(a, b)| (a - b).abs() < 0.001), "{:?} != {:?}", angles, expected);
+    }
+
+    #[test]
+    fn spreads_fan_out_around_the_aim() {
+        let spread = pattern(PatternShape::Spread, 5, 15.0, 0.0, 0.0);
+        assert_angles(spread.angles(90.0, 0), &[60.0, 75.0, 90.0, 105.0, 120.0]);
+        assert_angles(spread.angles(90.0, 7), &[60.0, 75.0, 90.0, 105.0, 120.0]);
+        let turning = pattern(PatternShape::Spread, 2, 10.0, 5.0, 0.0);
+        assert_angles(turning.angles(0.0, 2), &[5.0, 15.0]);
+    }
+
+    #[test]
+    fn rings_offset_every_other_volley_and_spirals_turn() {
+        let ring = pattern(PatternShape::Ring, 4, 0.0, 0.0, 0.0);
+        assert_angles(ring.angles(90.0, 0), &[90.0, 180.0, 270.0, 360.0]);
+        assert_angles(ring.angles(90.0, 1), &[135.0, 225.0, 315.0, 405.0]);
+        assert_angles(ring.angles(90.0, 2), &[90.0, 180.0, 270.0, 360.0]);
+        let spiral = pattern(PatternShape::Spiral, 3, 0.0, 13.0, 0.0);
+        assert_angles(spiral.angles(0.0, 0), &[0.0, 120.0, 240.0]);
+        assert_angles(spiral.angles(0.0, 2), &[26.0, 146.0, 266.0]);
+    }
+
+    #[test]
+    fn bursts_line_up_along_the_aim() {
+        let burst = pattern(PatternShape::AimedBurst, 3, 10.0, 0.0, 0.0);
+        assert_angles(burst.angles(45.0, 4), &[45.0, 45.0, 45.0]);
+        // fired straight down, each bullet starts 10 pixels behind the one before it
+        let mut platform = MockPlatform::new(1, vec![]);
+        let players = [Player::new(0, &platform)];
+        let bullets = burst.fire((100.0, 100.0), &players, 0, &mut GameRng::new(1), &mut platform);
+        let ys: Vec<f32> = bullets.iter().map(|bullet| bullet.hitbox.y + bullet.hitbox.h as f32 / 2.0).collect();
+        assert_angles(ys, &[100.0, 90.0, 80.0]);
+        assert!(bullets.iter().all(|bullet| bullet.hitbox.x + bullet.hitbox.w as f32 / 2.0 == 100.0));
+    }
+
+    #[test]
+    fn waves_sweep_back_and_forth_within_their_amplitude() {
+        let wave = pattern(PatternShape::Wave, 3, 20.0, 30.0, 45.0);
+        assert_angles(wave.angles(90.0, 0), &[70.0, 90.0, 110.0]);
+        assert_angles(wave.angles(90.0, 1), &[92.5, 112.5, 132.5]);
+        assert_angles(wave.angles(90.0, 3), &[115.0, 135.0, 155.0]);
+        assert_angles(wave.angles(90.0, 9), &[25.0, 45.0, 65.0]);
+    }
+
+    #[test]
+    fn aimed_patterns_point_at_the_nearest_player() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let players = [Player::new(0, &platform)];
+        // straight above the center of the first player's ship
+        let (x, y) = (players[0].hitbox.x + 8.0, players[0].hitbox.y - 100.0);
+        let bullets = BulletPattern::aimed(3.0).fire((x, y), &players, 0, &mut GameRng::new(1), &mut platform);
+        assert_eq!(bullets.len(), 1);
+        assert!((bullets[0].angle - 90.0).abs() < 0.001);
+        assert_eq!(bullets[0].velocity, 3.0);
+        assert_eq!(bullets[0].projectile_owner, ProjectileOwner::Enemy);
+    }
+}
@@ -0,0 +1,84 @@
+use super::*;
+
+// Options on the pause menu, from top to bottom
+#[turbo::serialize]
+#[derive(PartialEq, Copy)]
+pub enum PauseOption {
+    Resume,
+    Restart, // start the same level over
+    Options,
+    Quit, // back to the main menu, the run is abandoned
+}
+
+impl PauseOption {
+    pub const ALL: [PauseOption; 4] = [
+        PauseOption::Resume,
+        PauseOption::Restart,
+        PauseOption::Options,
+        PauseOption::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PauseOption::Resume => "RESUME",
+            PauseOption::Restart => "RESTART",
+            PauseOption::Options => "OPTIONS",
+            PauseOption::Quit => "QUIT TO MENU",
+        }
+    }
+}
+
+// Overlay menu shown while the game is paused
+#[turbo::serialize]
+pub struct PauseMenu {
+    pub selected: usize, // index into PauseOption::ALL
+}
+
+impl PauseMenu {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        PauseMenu { selected: 0 }
+    }
+
+    // Up and down move the selection and A picks it, tapping an option with the pointer picks it straight away
+    // START and B always resume
+    // Returns the option picked this frame, if any
+    pub fn update(&mut self, input: &Input, platform: &dyn Platform) -> Option<PauseOption> {
+        if input.just_pressed(Button::Start) || input.just_pressed(Button::B) {
+            return Some(PauseOption::Resume);
+        }
+        let count = PauseOption::ALL.len();
+        if input.just_pressed(Button::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.just_pressed(Button::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+        if let Some((px, py)) = input.pointer_just_pressed() {
+            let (px, py) = (px as f32, py as f32);
+            let screen = platform.resolution();
+            let tapped = (0..count).find(|&index| {
+                let bounds = Self::bounds(index, screen);
+                px >= bounds.x && px < bounds.x + bounds.w as f32 && py >= bounds.y && py < bounds.y + bounds.h as f32
+            });
+            let index = tapped?;
+            self.selected = index;
+            return Some(PauseOption::ALL[index]);
+        }
+        if input.just_pressed(Button::A) {
+            return Some(PauseOption::ALL[self.selected]);
+        }
+        None
+    }
+
+    // Area of the screen an option is drawn in, and can be tapped in
+    pub fn bounds(index: usize, screen: (u32, u32)) -> Hitbox {
+        let (screen_w, screen_h) = screen;
+        Hitbox {
+            x: (screen_w / 2 - 56) as f32,
+            y: (screen_h / 2 - 20 + index as u32 * 16) as f32,
+            w: 112,
+            h: 14,
+        }
+    }
+}
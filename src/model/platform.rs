@@ -0,0 +1,157 @@
+use super::*;
+
+// Everything the game logic needs from the outside world, apart from drawing
+// Game logic goes through this instead of calling turbo directly, so the simulation can also run headless
+pub trait Platform {
+    // Input for the current frame from each gamepad, called once per frame
+    fn input(&mut self) -> [InputFrame; MAX_PLAYERS];
+    fn play_sound(&mut self, name: &str);
+    fn shake_camera(&mut self, intensity: f32);
+    fn remove_shake(&mut self);
+    // Random number used to seed a new run
+    fn entropy(&mut self) -> u32;
+    fn resolution(&self) -> (u32, u32);
+    // Data kept between sessions, None if nothing has been saved yet
+    fn load_storage(&mut self) -> Option<Vec<u8>>;
+    // Replaces everything saved before
+    fn save_storage(&mut self, data: &[u8]);
+}
+
+// Platform backed by the turbo runtime, used when running the game
+// Built every frame from the current settings, so volume and shake changes apply straight away
+pub struct TurboPlatform {
+    pub settings: Settings,
+}
+
+impl TurboPlatform {
+    pub fn new(settings: &Settings) -> Self {
+        TurboPlatform {
+            settings: settings.clone(),
+        }
+    }
+}
+
+impl Platform for TurboPlatform {
+    // Reads the current state of each gamepad, and the pointer for the first one
+    fn input(&mut self) -> [InputFrame; MAX_PLAYERS] {
+        let mut frames: [InputFrame; MAX_PLAYERS] = Default::default();
+        for (index, frame) in frames.iter_mut().enumerate() {
+            let gamepad = gamepad::get(index);
+            frame.set(Button::Up, gamepad.up.pressed());
+            frame.set(Button::Down, gamepad.down.pressed());
+            frame.set(Button::Left, gamepad.left.pressed());
+            frame.set(Button::Right, gamepad.right.pressed());
+            frame.set(Button::A, gamepad.a.pressed());
+            frame.set(Button::B, gamepad.b.pressed());
+            frame.set(Button::X, gamepad.x.pressed());
+            frame.set(Button::Y, gamepad.y.pressed());
+            frame.set(Button::Start, gamepad.start.pressed());
+            frame.set(Button::Select, gamepad.select.pressed());
+        }
+        let pointer = pointer::screen();
+        if pointer.pressed() {
+            frames[0].pointer = Some(pointer.xy());
+        }
+        frames
+    }
+
+    fn play_sound(&mut self, name: &str) {
+        audio::set_volume(name, self.settings.volume(name));
+        audio::play(name);
+    }
+
+    fn shake_camera(&mut self, intensity: f32) {
+        let intensity = intensity * self.settings.shake_scale();
+        if intensity > 0.0 {
+            camera::shake(intensity);
+        }
+    }
+
+    fn remove_shake(&mut self) {
+        camera::remove_shake();
+    }
+
+    fn entropy(&mut self) -> u32 {
+        random::u32()
+    }
+
+    fn resolution(&self) -> (u32, u32) {
+        resolution()
+    }
+
+    // Turbo keeps one blob per game, it's empty until something is saved
+    fn load_storage(&mut self) -> Option<Vec<u8>> {
+        local::load().ok().filter(|data| !data.is_empty())
+    }
+
+    fn save_storage(&mut self, data: &[u8]) {
+        let _ = local::save(data);
+    }
+}
+
+// Scripted platform for running the game without turbo, e.g. in native tests
+// Feeds input from a script one frame at a time and records the sounds and camera shake the game asks for
+pub struct MockPlatform {
+    pub script: Vec<InputFrame>, // input for each frame, nothing is pressed once the script runs out
+    pub partner_script: Vec<InputFrame>, // same as script, for the second gamepad
+    pub frame: usize, // index of the next frame of the script
+    pub seed: u32, // returned as entropy, so every run is seeded the same
+    pub screen: (u32, u32),
+    pub sounds: Vec<String>, // every sound played, in order
+    pub shake: Option<f32>, // current camera shake, None when the camera is still
+    pub storage: Option<Vec<u8>>, // last data saved, kept in memory
+}
+
+impl MockPlatform {
+    pub fn new(seed: u32, script: Vec<InputFrame>) -> Self {
+        MockPlatform {
+            script,
+            partner_script: vec![],
+            frame: 0,
+            seed,
+            screen: (256, 512), // same as the canvas in turbo.toml
+            sounds: vec![],
+            shake: None,
+            storage: None,
+        }
+    }
+}
+
+impl Platform for MockPlatform {
+    fn input(&mut self) -> [InputFrame; MAX_PLAYERS] {
+        let frames = [
+            self.script.get(self.frame).cloned().unwrap_or_default(),
+            self.partner_script.get(self.frame).cloned().unwrap_or_default(),
+        ];
+        self.frame += 1;
+        frames
+    }
+
+    fn play_sound(&mut self, name: &str) {
+        self.sounds.push(name.to_string());
+    }
+
+    fn shake_camera(&mut self, intensity: f32) {
+        self.shake = Some(intensity);
+    }
+
+    fn remove_shake(&mut self) {
+        self.shake = None;
+    }
+
+    fn entropy(&mut self) -> u32 {
+        self.seed
+    }
+
+    fn resolution(&self) -> (u32, u32) {
+        self.screen
+    }
+
+    fn load_storage(&mut self) -> Option<Vec<u8>> {
+        self.storage.clone()
+    }
+
+    fn save_storage(&mut self, data: &[u8]) {
+        self.storage = Some(data.to_vec());
+    }
+}
@@ -1,7 +1,31 @@
-use std::sync::Arc;
-
 use super::*;
 
+// Most players that can be in a run at once, each on their own gamepad
+pub const MAX_PLAYERS: usize = 2;
+
+// Frames a partner has to stay over a downed player's ship to revive them
+pub const REVIVE_FRAMES: u32 = 60 * 2;
+
+// Key of a player's ship, used for its animation and to attach its laser beam
+pub fn player_key(index: usize) -> String {
+    format!("player{}", index + 1)
+}
+
+// The player enemies aim at from a point: the nearest one still flying, or the nearest wreck once everyone is down
+pub fn nearest_player(players: &[Player], x: f32, y: f32) -> &Player {
+    let distance = |player: &&Player| {
+        let dx = player.hitbox.x + player.hitbox.w as f32 / 2.0 - x;
+        let dy = player.hitbox.y + player.hitbox.h as f32 / 2.0 - y;
+        dx * dx + dy * dy
+    };
+    let flying = players.iter().any(|player| player.hp > 0);
+    players
+        .iter()
+        .filter(|player| player.hp > 0 || !flying)
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .expect("a run always has a player")
+}
+
 #[turbo::serialize]
 pub struct PlayerStats {
     pub max_hp: u32,
@@ -9,11 +33,14 @@ pub struct PlayerStats {
     pub damage: u32,
     pub rate_of_fire: u32,
     pub projectile_speed: u32,
+    pub projectile_size: u32,
+    pub pierce: u32,
 }
 
 // Struct for Player properties
 #[turbo::serialize]
 pub struct Player {
+    pub index: usize, // 0 for the first player, 1 for the partner who dropped in
     pub hitbox: Hitbox,
     dx: f32, // dx and dy used for movement
     dy: f32,
@@ -21,26 +48,44 @@ pub struct Player {
     pub hp: u32,
     
     pub hit_timer: u32, // used for invincibility frames and drawing
+    pub revive: u32, // frames a partner has spent over the ship while it's down, it's revived at REVIVE_FRAMES
     shoot_timer: u32, // used for rate of fire
+    bomb_timer: u32, // cooldown of the bomb action, kept apart so bombs don't hold up the selected weapon
     shooting: bool, // used for shooting animation
     
-    pub stats: PlayerStats,
+    pub base_stats: PlayerStats, // stats including permanent upgrades
+    pub stats: PlayerStats, // effective stats, recomputed each frame from base stats and active buffs
+    pub buffs: Vec<Buff>,
     
-    // TODO: add functionality for different projectile types
-    pub projectile_type: ProjectileType,
+    // Weapon inventory, the weapon at weapon_index is the one being fired
+    pub weapons: Vec<Weapon>,
+    pub weapon_index: usize,
     
     // variables used by the HUD to display information
     pub score: u32,
     pub notifications: Vec<String>,
+
+    pub run_stats: RunStats, // statistics for the balance simulator
 }
 
 impl Player {
-    pub fn new() -> Self {
-        let (screen_w, screen_h) = resolution();
+    // The partner starts off to the side of where the first player starts
+    pub fn new(index: usize, platform: &dyn Platform) -> Self {
+        let (screen_w, screen_h) = platform.resolution();
+        let stats = PlayerStats {
+            max_hp: 3,
+            speed: 2.0,
+            damage: 1,
+            rate_of_fire: 15,
+            projectile_speed: 5,
+            projectile_size: 6,
+            pierce: 0,
+        };
         Player {
             // Initialize all fields with default values
+            index,
             hitbox: Hitbox {
-                x: ((screen_w / 2) - 8) as f32,
+                x: ((screen_w / 2) - 8) as f32 + index as f32 * 32.0,
                 y: (screen_h - 64) as f32,
                 w: 16,
                 h: 16,
@@ -50,49 +95,58 @@ impl Player {
             hp: 3,
             
             hit_timer: 0,
+            revive: 0,
             shoot_timer: 0,
+            bomb_timer: 0,
             shooting: false,
             
-            stats: PlayerStats {
-                max_hp: 3,
-                speed: 2.0,
-                damage: 1,
-                rate_of_fire: 15,
-                projectile_speed: 5,
-            },
+            base_stats: stats.clone(),
+            stats,
+            buffs: vec![],
 
-            projectile_type: ProjectileType::Basic,
+            weapons: vec![Weapon::new(ProjectileType::Basic)],
+            weapon_index: 0,
             
             score: 0,
-            notifications: vec![
-                "Use arrow keys to move.".to_string(),
-                "Press SPACE or A to shoot.".to_string(),
-                "Defeat enemies and collect powerups.".to_string(),
-                "Try to not die. Good luck!".to_string(),
-            ],
+            // only the first player gets the tutorial, the partner drops in partway through
+            notifications: if index == 0 {
+                vec![
+                    "Use arrow keys to move.".to_string(),
+                    "Press SPACE or A to shoot.".to_string(),
+                    "Defeat enemies and collect powerups.".to_string(),
+                    "Try to not die. Good luck!".to_string(),
+                ]
+            } else {
+                vec![]
+            },
+
+            run_stats: RunStats::default(),
         }
     }
     // update is called once per frame within the [turbo::game] loop
-    pub fn update(&mut self, projectiles: &mut Vec<Projectile>, powerups: &mut Vec<Powerup>, enemies: &mut Vec<Enemy>) {
-        let (screen_w, screen_h) = resolution();
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(&mut self, input: &Actions, bindings: &Bindings, projectiles: &mut Vec<Projectile>, powerups: &mut Vec<Powerup>, enemies: &mut [Enemy], grids: &Grids, rng: &mut GameRng, platform: &mut dyn Platform) {
+        let (screen_w, screen_h) = platform.resolution();
+        // Tick down active buffs before using any stats this frame
+        self.update_buffs();
         if self.hp != 0 {
+            self.run_stats.update(self.score);
             // Player movement handling (normalized for diagonal movement)
             self.dx = 0.0;
             self.dy = 0.0;
-            if gamepad::get(0).up.pressed() {
+            if input.pressed(Action::MoveUp) {
                 self.dy -= 1.0;
             }
-            if gamepad::get(0).down.pressed() {
+            if input.pressed(Action::MoveDown) {
                 self.dy += 1.0;
             }
-            if gamepad::get(0).left.pressed() {
+            if input.pressed(Action::MoveLeft) {
                 self.dx -= 1.0;
             }
-            if gamepad::get(0).right.pressed() {
+            if input.pressed(Action::MoveRight) {
                 self.dx += 1.0;
             }
-            if pointer::screen().pressed() {
-                let (px, py) = pointer::screen().xy();
+            if let Some((px, py)) = input.pointer() {
                 if (px as f32) < self.hitbox.x + self.hitbox.w as f32 / 2.0 - (self.stats.speed / 2.0) {
                     self.dx -= 1.0;
                 } else if (px as f32) >= self.hitbox.x + self.hitbox.w as f32 / 2.0 + (self.stats.speed / 2.0) {
@@ -104,9 +158,10 @@ impl Player {
                     self.dy += 1.0;
                 }
             }
-            let len = ((self.dx as f32) * (self.dx as f32) + (self.dy as f32) * (self.dy as f32)).sqrt();
+            let len = (self.dx * self.dx + self.dy * self.dy).sqrt();
             if len > 0.0 {
-                let speed = self.stats.speed;
+                // Focus slows the ship down for careful dodging
+                let speed = if input.pressed(Action::Focus) { self.stats.speed / 2.0 } else { self.stats.speed };
                 let nx = self.dx / len;
                 let ny = self.dy / len;
                 self.hitbox.x = (self.hitbox.x + nx * speed).clamp(0.0, (screen_w - self.hitbox.w) as f32);
@@ -114,93 +169,211 @@ impl Player {
             }
 
             // Shooting projectiles
-            // check if shoot button is pressed
-            if gamepad::get(0).start.pressed() || gamepad::get(0).a.pressed() || pointer::screen().pressed() {
+            // check if fire is held, or the pointer is pressed
+            if input.pressed(Action::Fire) || input.pointer().is_some() {
                 self.shooting = true; // flag shooting state for animation
-                // if shoot timer is 0, shoot a projectile
-                if self.shoot_timer == 0 {
-                    self.shoot_timer += self.stats.rate_of_fire; // reset shoot timer
-                    for i in 0..=1 {
-                        projectiles.push(
-                            Projectile::new(
-                                self.hitbox.x + i as f32 * 13.0,
-                                self.hitbox.y - 8.0,
-                                5.0,
-                                -90.0,
-                                self.projectile_type.clone(),
-                                ProjectileOwner::Player,
-                            )
-                        );
-                    }
-                    audio::play("projectile_player_shoot");
+                let weapon = &mut self.weapons[self.weapon_index];
+                // Lasers fire continuously, everything else waits for the shoot timer
+                if weapon.projectile_type == ProjectileType::Laser || self.shoot_timer == 0 {
+                    self.fire_weapon(self.weapon_index, projectiles, rng, platform);
                 }
             // if not shooting
             } else {
                 self.shooting = false; // flag shooting state for animation
             }
+            // The bomb button drops a bomb whichever weapon is selected, on its own cooldown instead of the shoot timer
+            if input.just_pressed(Action::Bomb) && self.bomb_timer == 0 {
+                let bomb = self.weapons.iter().position(|weapon| weapon.projectile_type == ProjectileType::Bomb);
+                if let Some(index) = bomb {
+                    self.fire_weapon(index, projectiles, rng, platform);
+                }
+            }
             // decrement shoot timer
             self.shoot_timer = self.shoot_timer.saturating_sub(1);
+            self.bomb_timer = self.bomb_timer.saturating_sub(1);
+
+            // Cycle through weapons
+            let count = self.weapons.len();
+            if input.just_pressed(Action::NextWeapon) {
+                self.weapon_index = (self.weapon_index + 1) % count;
+            }
+            if input.just_pressed(Action::PreviousWeapon) {
+                self.weapon_index = (self.weapon_index + count - 1) % count;
+            }
         }
         
         // Handle player collecting power-ups
-        // Iterate through all spawned power-ups, removing any the player collides with
+        // Iterate through the power-ups near the player, removing any the player collides with
+        // The grid has to be built from the powerups as they are now, for the indices to line up
+        let nearby = grids.powerups.query(&self.hitbox);
+        let mut index = 0;
         powerups.retain(|powerup| {
+            index += 1;
             // If player collides with this power-up
-            if check_collision(
+            if nearby.contains(&(index - 1)) && check_collision(
                 &self.hitbox,
                 &powerup.hitbox,
             ) {
                 // Player collects this power-up
-                self.collect_powerup(powerup);
+                self.collect_powerup(powerup, bindings);
+                self.run_stats.record_pickup(&powerup.effect);
+                self.recompute_stats();
                 false // Remove this power-up after it's picked up
             } else {
                 true
             }
         });
 
-        // Iterate through enemies, checking if any collide with the player
-        enemies.iter_mut().for_each(|enemy| {
-            if enemy.hp > 0 && check_collision(
-                &self.hitbox,
-                &enemy.hitbox,
-            ) {
+        // Iterate through the enemies near the player, checking if any collide with the player
+        for index in grids.enemies.query(&self.hitbox) {
+            let enemy = &mut enemies[index];
+            if enemy.hp > 0 && enemy.collides(&Collider::new(&self.hitbox, Shape::Rect)) {
                 // Collision detected, both take damage
-                self.take_damage(1);
-                enemy.take_damage(self, enemy.hp);
+                if enemy.boss.is_none() {
+                    self.take_damage(1, &Some(enemy.enemy_type.clone()), platform);
+                    enemy.take_damage(self, enemy.hp);
+                }
+                // Bosses are too big to ram, they hurt the player like a projectile would
+                else if self.hit_timer == 0 {
+                    self.take_damage(1, &Some(enemy.enemy_type.clone()), platform);
+                }
             }
-        });
+        }
 
         // hit timer
-        self.hit_timer = self.hit_timer.saturating_sub(1);
-        // Remove the camera shake
-        if self.hit_timer == 0 {
-            camera::remove_shake();
+        if self.hit_timer > 0 {
+            self.hit_timer -= 1;
+            // Remove the camera shake once the timer runs out
+            if self.hit_timer == 0 {
+                platform.remove_shake();
+            }
+        }
+    }
+
+    // Counts down active buffs, removes expired ones, and recomputes the effective stats
+    fn update_buffs(&mut self) {
+        for buff in self.buffs.iter_mut() {
+            buff.remaining = buff.remaining.saturating_sub(1);
+        }
+        // Notify the player when a buff wears off
+        for buff in self.buffs.iter().filter(|buff| buff.remaining == 0) {
+            self.notifications.push(format!("{} ENDED", buff.kind.name()));
+        }
+        self.buffs.retain(|buff| buff.remaining > 0);
+        self.recompute_stats();
+    }
+
+    // Effective stats are the base stats with every active buff applied on top
+    fn recompute_stats(&mut self) {
+        self.stats = self.base_stats.clone();
+        for buff in &self.buffs {
+            buff.apply(&mut self.stats);
+        }
+    }
+
+    // Adds a stack of a buff, or starts it if it isn't active, refreshing its duration either way
+    pub fn add_buff(&mut self, kind: BuffKind) {
+        match self.buffs.iter_mut().find(|buff| buff.kind == kind) {
+            Some(buff) => {
+                buff.stacks = (buff.stacks + 1).min(kind.max_stacks());
+                buff.remaining = kind.duration();
+            }
+            None => self.buffs.push(Buff::new(kind)),
+        }
+        self.recompute_stats();
+    }
+
+    // Current number of stacks of a buff, 0 if it isn't active
+    pub fn buff_stacks(&self, kind: BuffKind) -> u32 {
+        self.buffs
+            .iter()
+            .find(|buff| buff.kind == kind)
+            .map_or(0, |buff| buff.stacks)
+    }
+
+    // Starts the run with a different max HP, at full health
+    pub fn set_max_hp(&mut self, max_hp: u32) {
+        self.base_stats.max_hp = max_hp;
+        self.stats.max_hp = max_hp;
+        self.hp = max_hp;
+    }
+
+    // Fires one of the player's weapons, resetting the shoot timer if it's the selected one and the bomb timer for bombs
+    // The weapon is dropped once it runs dry, falling back to the blaster if it was the selected one
+    fn fire_weapon(&mut self, index: usize, projectiles: &mut Vec<Projectile>, rng: &mut GameRng, platform: &mut dyn Platform) {
+        let weapon = &mut self.weapons[index];
+        let cooldown = (self.stats.rate_of_fire as f32 * weapon.cooldown) as u32;
+        if index == self.weapon_index {
+            self.shoot_timer = cooldown;
+        }
+        if weapon.projectile_type == ProjectileType::Bomb {
+            self.bomb_timer = cooldown;
+        }
+        weapon.fire(self.index, &self.hitbox, &self.stats, projectiles, rng, platform);
+        if weapon.projectile_type != ProjectileType::Laser {
+            platform.play_sound("projectile_player_shoot");
+        }
+        if weapon.out_of_ammo() {
+            self.notifications.push(format!("OUT OF {} AMMO", weapon.name()));
+            self.weapons.remove(index);
+            if self.weapon_index == index {
+                self.weapon_index = 0;
+            } else if self.weapon_index > index {
+                self.weapon_index -= 1;
+            }
         }
     }
 
-    pub fn reset(&mut self) -> bool {
+    pub fn reset(&mut self, input: &Actions, platform: &mut dyn Platform) -> bool {
         self.hit_timer = self.hit_timer.saturating_sub(1);
         // Remove the camera shake
         if self.hit_timer == 0 {
-            camera::remove_shake();
+            platform.remove_shake();
         }
         // Restart
         if self.hit_timer == 0 
-        && (gamepad::get(0).start.just_pressed()
-        || gamepad::get(0).a.just_pressed())
+        && (input.just_pressed(Action::Pause)
+        || input.just_pressed(Action::Fire))
         {
             return true;
         }
         false
     }
+    // Called every frame while the player is down in co-op, rescued is whether a partner is over the ship
+    // Progress drains away twice as fast while nobody is, and the player is back with 1 HP once it fills up
+    pub fn update_revive(&mut self, rescued: bool, platform: &mut dyn Platform) {
+        // the hit timer keeps running down, so the camera stops shaking
+        if self.hit_timer > 0 {
+            self.hit_timer -= 1;
+            if self.hit_timer == 0 {
+                platform.remove_shake();
+            }
+        }
+        if rescued {
+            self.revive += 1;
+        } else {
+            self.revive = self.revive.saturating_sub(2);
+        }
+        if self.revive >= REVIVE_FRAMES {
+            self.revive = 0;
+            self.hp = 1;
+            self.hit_timer = 60; // a second of invincibility to get clear
+            self.notifications.push("REVIVED!".to_string());
+        }
+    }
     // Function to handle player taking damage
-    pub fn take_damage(&mut self, damage: u32) {
+    // source is the type of enemy that dealt the damage, if known
+    pub fn take_damage(&mut self, damage: u32, source: &Option<String>, platform: &mut dyn Platform) {
         self.hp = self.hp.saturating_sub(damage); // reduce HP by damage amount
-        camera::shake(5.0); // camera shake
+        if self.hp == 0 && self.run_stats.killed_by.is_none() {
+            self.run_stats.killed_by = source.clone();
+        }
+        platform.shake_camera(5.0); // camera shake
         self.hit_timer = 20; // invincibility frame timer and drawing flag
     }
     // Function to handle player collecting a power-up
-    pub fn collect_powerup(&mut self, powerup: &Powerup) {
+    // bindings are the player's controls, for the buttons named in the notifications
+    pub fn collect_powerup(&mut self, powerup: &Powerup, bindings: &Bindings) {
         // Apply the effect based on the power-up type
         match &powerup.effect {
             // Heal player
@@ -210,36 +383,79 @@ impl Player {
             }
             // Heal and increase max HP
             PowerupEffect::MaxHealthUp => {
-                self.stats.max_hp = (self.stats.max_hp + 1).min(10);
+                self.base_stats.max_hp = (self.base_stats.max_hp + 1).min(10);
                 self.hp += 1;
                 self.notifications.push("MAX HP +1".to_string());
             }
-            // Increase speed
+            // Temporarily increase speed
             PowerupEffect::SpeedBoost => {
-                self.stats.speed *= 1.1;
-                self.notifications.push("1.1x SPEED BOOST".to_string());
+                self.add_buff(BuffKind::Speed);
+                self.notifications.push(format!("SPEED BOOST x{}", self.buff_stacks(BuffKind::Speed))); // stacks, as counted on the HUD, not the multiplier
             }
-            // Increase damage
+            // Temporarily increase damage
             PowerupEffect::DamageBoost => {
-                self.notifications.push(format!("+1 DAMAGE"));
-                self.stats.damage = (self.stats.damage + 1).min(2);
+                self.add_buff(BuffKind::Damage);
+                self.notifications.push(format!("+{} DAMAGE BOOST", self.buff_stacks(BuffKind::Damage)));
             }
             // Increase rate of fire
             PowerupEffect::RateOfFireBoost => {
-                self.stats.rate_of_fire = (self.stats.rate_of_fire.saturating_sub(1)).max(5);
+                self.base_stats.rate_of_fire = (self.base_stats.rate_of_fire.saturating_sub(1)).max(5);
                 self.notifications.push("+1 RATE OF FIRE".to_string());
             }
             // Increase projectile speed
             PowerupEffect::ProjectileSpeedBoost => {
-                self.stats.projectile_speed = (self.stats.projectile_speed + 1).min(10);
+                self.base_stats.projectile_speed = (self.base_stats.projectile_speed + 1).min(10);
                 self.notifications.push("+1 PROJECTILE SPEED".to_string());
             }
+            // Increase the number of enemies shots can pass through
+            PowerupEffect::PierceBoost => {
+                self.base_stats.pierce = (self.base_stats.pierce + 1).min(3);
+                self.notifications.push("+1 PIERCE".to_string());
+            }
+            // Make shots bigger and easier to land, every weapon grows by the same amount
+            PowerupEffect::ProjectileSizeBoost => {
+                self.base_stats.projectile_size = (self.base_stats.projectile_size + 2).min(12);
+                self.notifications.push("+2 PROJECTILE SIZE".to_string());
+            }
+            // Add a new weapon to the inventory, or refill its ammo if the player already has it
+            PowerupEffect::Weapon(projectile_type) => {
+                match self.weapons.iter_mut().find(|weapon| weapon.projectile_type == *projectile_type) {
+                    Some(weapon) => {
+                        weapon.ammo = weapon.max_ammo();
+                        self.notifications.push(format!("{} AMMO REFILLED", weapon.name()));
+                    }
+                    None => {
+                        let weapon = Weapon::new(projectile_type.clone());
+                        let hint = match projectile_type {
+                            ProjectileType::Bomb => format!("{} TO DROP", bindings.button(Action::Bomb).name()),
+                            _ => format!(
+                                "{}/{} TO SWITCH",
+                                bindings.button(Action::NextWeapon).name(),
+                                bindings.button(Action::PreviousWeapon).name()
+                            ),
+                        };
+                        self.notifications.push(format!("GOT {}! {}", weapon.name(), hint));
+                        self.weapons.push(weapon);
+                    }
+                }
+            }
         }
     }
 
-    pub fn draw(&self) {
+    // Tint that tells the players apart, the partner's ship is blue
+    pub fn tint(&self) -> u32 {
+        match self.index {
+            0 => 0xffffffff,
+            _ => 0x88ccffff,
+        }
+    }
+
+    // flash_color is what the ship is tinted while flashing from a hit
+    // A downed ship is drawn dimmed, with a ring filling up as a partner revives it
+    pub fn draw(&self, flash_color: u32) {
         // get reference to SpriteAnimation for player
-        let anim = animation::get("player");
+        let key = player_key(self.index);
+        let anim = animation::get(&key);
         
         // begin to construct the string for which sprite to use
         let mut sprite = String::from("player/player");
@@ -256,15 +472,28 @@ impl Player {
         // Assign the sprite string to the SpriteAnimation
         anim.use_sprite(&sprite);
         // Flash red when hit
-        let color = if self.hit_timer > 0 && self.hit_timer % 10 < 5 {
-            0xff0000ff
+        let color = if self.hp == 0 {
+            0x555555aa
+        } else if self.hit_timer > 0 && self.hit_timer % 10 < 5 {
+            flash_color
         } else {
-            0xffffffff
+            self.tint()
         };
         sprite!(
-            animation_key = "player",
+            animation_key = &key,
             x = self.hitbox.x,
             y = self.hitbox.y,
             color = color);
+        if self.hp == 0 && self.revive > 0 {
+            let size = 24 * self.revive / REVIVE_FRAMES + 8;
+            circ!(
+                d = size,
+                x = self.hitbox.x + (self.hitbox.w as f32 - size as f32) / 2.0,
+                y = self.hitbox.y + (self.hitbox.h as f32 - size as f32) / 2.0,
+                color = 0x00000000,
+                border_size = 1,
+                border_color = self.tint()
+            );
+        }
     }
 }
@@ -2,13 +2,17 @@ use super::*;
 
 
 #[turbo::serialize]
+#[derive(PartialEq)]
 pub enum PowerupEffect {
     Heal,                        // Heals the player when interacted with
     MaxHealthUp,                 // Increases max health
-    SpeedBoost,                  // Temporarily increases player's speed
-    DamageBoost,                 // Temporarily increases projectile's damage
+    SpeedBoost,                  // Temporarily increases player's speed, stacking up to 3 times
+    DamageBoost,                 // Temporarily increases projectile's damage, stacking up to 2 times
     RateOfFireBoost,
     ProjectileSpeedBoost,
+    PierceBoost,                 // Lets shots pass through one more enemy
+    ProjectileSizeBoost,         // Makes shots 2px bigger, up to 12px
+    Weapon(ProjectileType),      // Adds a weapon to the player's inventory, or refills its ammo
 }
 
 #[turbo::serialize]
@@ -33,13 +37,13 @@ impl Powerup {
         }
     }
 
-    pub fn new_random(x: f32, y: f32, player: &Player) -> Self {
+    pub fn new_random(x: f32, y: f32, player: &Player, rng: &mut GameRng) -> Self {
         // Create a list of possible effects based on player's current stats
         let mut effects = vec![
             PowerupEffect::SpeedBoost,
         ];
         // Add max HP if the player has less than 5 max HP
-        if player.stats.max_hp < 5 {
+        if player.base_stats.max_hp < 5 {
             effects.push(PowerupEffect::MaxHealthUp);
         }
         // Add other effects based on score thresholds
@@ -49,16 +53,35 @@ impl Powerup {
         if player.score > 800 {
             effects.push(PowerupEffect::ProjectileSpeedBoost);
         }
-        if player.score > 1500 && player.stats.damage < 3 {
+        if player.score > 1500 && player.buff_stacks(BuffKind::Damage) < BuffKind::Damage.max_stacks() {
             effects.push(PowerupEffect::DamageBoost);
         }
+        if player.score > 1000 && player.base_stats.pierce < 3 {
+            effects.push(PowerupEffect::PierceBoost);
+        }
+        if player.score > 700 && player.base_stats.projectile_size < 12 {
+            effects.push(PowerupEffect::ProjectileSizeBoost);
+        }
+        // Unlock weapon drops as the score goes up
+        if player.score > 200 {
+            effects.push(PowerupEffect::Weapon(ProjectileType::Splatter));
+        }
+        if player.score > 600 {
+            effects.push(PowerupEffect::Weapon(ProjectileType::Fragment));
+        }
+        if player.score > 1000 {
+            effects.push(PowerupEffect::Weapon(ProjectileType::Bomb));
+        }
+        if player.score > 1200 {
+            effects.push(PowerupEffect::Weapon(ProjectileType::Laser));
+        }
         // Select a random effect from the constructed list
-        let effect = effects[(random::u32() as usize) % effects.len()].clone();
+        let effect = effects[(rng.loot.u32() as usize) % effects.len()].clone();
         // Randomly choose a movement type between the 3 options
-        let movement = match random::u32() % 3 {
+        let movement = match rng.loot.u32() % 3 {
             0 => PowerupMovement::Static,
-            1 => PowerupMovement::FloatVertical(0.5 + (random::u32() % 100) as f32 * 0.01),
-            2 => PowerupMovement::FloatHorizontal(0.5 + (random::u32() % 100) as f32 * 0.01),
+            1 => PowerupMovement::FloatVertical(0.5 + (rng.loot.u32() % 100) as f32 * 0.01),
+            2 => PowerupMovement::FloatHorizontal(0.5 + (rng.loot.u32() % 100) as f32 * 0.01),
             _ => unreachable!(),
         };
         Powerup {
@@ -68,8 +91,8 @@ impl Powerup {
         }
     }
     // update is called once per frame within the [turbo::game] loop
-    pub fn update(&mut self) {
-        let (screen_w, screen_h) = resolution();
+    pub fn update(&mut self, platform: &dyn Platform) {
+        let (screen_w, screen_h) = platform.resolution();
         // update position based on movement type
         match self.movement {
             PowerupMovement::FloatVertical(speed) => {
@@ -101,6 +124,13 @@ impl Powerup {
             PowerupEffect::SpeedBoost => "power_ups/powerup_speed",
             PowerupEffect::RateOfFireBoost => "power_ups/powerup_rate_of_fire",
             PowerupEffect::ProjectileSpeedBoost => "power_ups/powerup_projectile_speed",
+            PowerupEffect::PierceBoost => "power_ups/powerup_pierce",
+            PowerupEffect::ProjectileSizeBoost => "power_ups/powerup_projectile_size",
+            PowerupEffect::Weapon(ProjectileType::Basic) => "power_ups/powerup_weapon_basic",
+            PowerupEffect::Weapon(ProjectileType::Splatter) => "power_ups/powerup_weapon_splatter",
+            PowerupEffect::Weapon(ProjectileType::Fragment) => "power_ups/powerup_weapon_fragment",
+            PowerupEffect::Weapon(ProjectileType::Laser) => "power_ups/powerup_weapon_laser",
+            PowerupEffect::Weapon(ProjectileType::Bomb) => "power_ups/powerup_weapon_bomb",
         };
         // Small oscillating value for bobbing effect
         let n = (tick as f32 * 0.15).cos() * 3.0;
@@ -1,6 +1,5 @@
 use super::*;
 
-// TODO: add different behaviors for different projectile types
 #[turbo::serialize]
 #[derive(PartialEq)]
 pub enum ProjectileType {
@@ -15,7 +14,49 @@ pub enum ProjectileType {
 #[derive(PartialEq)]
 pub enum ProjectileOwner {
     Enemy,
-    Player,
+    Player(usize), // index of the player who fired it, credited with whatever it destroys
+}
+
+impl ProjectileOwner {
+    // Whether both are on the same side, every player's projectiles are allied with each other
+    pub fn allied(&self, other: &ProjectileOwner) -> bool {
+        matches!(
+            (self, other),
+            (ProjectileOwner::Enemy, ProjectileOwner::Enemy) | (ProjectileOwner::Player(_), ProjectileOwner::Player(_))
+        )
+    }
+}
+
+// How a Fragment shell breaks apart into shrapnel
+#[turbo::serialize]
+pub struct Shrapnel {
+    pub count: u32, // number of child projectiles
+    pub spread: f32, // total angle in degrees the children fan out across
+    pub range: f32, // distance the shell travels before bursting on its own
+}
+
+// How a Laser beam behaves while attached to the ship that fired it
+#[turbo::serialize]
+pub struct Beam {
+    pub anchor: String, // key of the ship the beam is attached to, a player's or an enemy's
+    pub warmup: u32, // frames spent telegraphing with a thin warning line before turning lethal
+    pub lifetime: u32, // frames the beam stays lethal, refreshed by the player while holding fire
+    pub tick_rate: u32, // frames between damage ticks
+}
+
+// How a Bomb counts down to its explosion
+#[turbo::serialize]
+pub struct Fuse {
+    pub length: u32, // total frames from launch to explosion, used to draw the arc
+    pub timer: u32, // frames left until the bomb explodes
+    pub radius: f32, // blast radius in pixels
+    pub clears_projectiles: bool, // whether the blast destroys opposing projectiles caught in it
+}
+
+// Requests from a projectile to the GameState, handled once all projectiles have been updated
+pub enum ProjectileEvent {
+    Spawn(Projectile), // add a new projectile, e.g. shrapnel from a Fragment shell
+    Blast { x: f32, y: f32, radius: f32, owner: ProjectileOwner }, // destroy opposing projectiles caught in an explosion
 }
 
 #[turbo::serialize]
@@ -25,110 +66,702 @@ pub struct Projectile {
 
     pub collided: bool, // Used to control the sprite and update state
     pub destroyed: bool, // Used to remove projectile from game
+    timer: u32, // frames spent in the current state, used by lingering effects like Splatter puddles and Laser beams
 
     pub velocity: f32,
-    angle: f32,
+    pub angle: f32,
+    distance: f32, // total distance travelled
     pub damage: u32,
+    pub pierce: u32, // number of enemies the projectile can pass through before colliding
+    hit_keys: Vec<String>, // keys of enemies already pierced, so they aren't hit twice
     pub projectile_owner: ProjectileOwner,
     pub projectile_type: ProjectileType,
+    pub source: Option<String>, // type of the enemy that fired the projectile, None for the player's
+    pub shape: Shape, // shape the projectile collides as
+    pub shrapnel: Option<Shrapnel>, // only set for Fragment shells
+    pub beam: Option<Beam>, // only set for Laser beams
+    pub fuse: Option<Fuse>, // only set for Bombs
 }
 
 impl Projectile {
-    pub fn new(x: f32, y: f32, velocity: f32, angle: f32, projectile_type: ProjectileType, projectile_owner: ProjectileOwner) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(x: f32, y: f32, velocity: f32, angle: f32, projectile_type: ProjectileType, projectile_owner: ProjectileOwner, rng: &mut GameRng, platform: &mut dyn Platform) -> Self {
         let audio = match projectile_owner {
             ProjectileOwner::Enemy => "projectile_enemy_shoot",
-            ProjectileOwner::Player => "projectile_player_shoot",
+            ProjectileOwner::Player(_) => "projectile_player_shoot",
+        };
+        platform.play_sound(audio);
+        // Splatter globs, Fragment shells and Bombs are a bit bigger than regular shots
+        let size = match projectile_type {
+            ProjectileType::Splatter | ProjectileType::Fragment | ProjectileType::Bomb => 8,
+            _ => 6,
+        };
+        // Fragment shells burst into 5 pieces across a 90 degree fan
+        let shrapnel = match projectile_type {
+            ProjectileType::Fragment => Some(Shrapnel {
+                count: 5,
+                spread: 90.0,
+                range: 160.0,
+            }),
+            _ => None,
+        };
+        // Bombs explode after 1 second, clearing out enemy fire caught in the blast
+        let fuse = match projectile_type {
+            ProjectileType::Bomb => Some(Fuse {
+                length: 60,
+                timer: 60,
+                radius: 32.0,
+                clears_projectiles: true,
+            }),
+            _ => None,
+        };
+        // Bullets are round, beams are long and thin
+        let shape = match projectile_type {
+            ProjectileType::Laser => Shape::Capsule,
+            _ => Shape::Circle,
+        };
+        // Bombs hit hardest at the center of their blast
+        let damage = match projectile_type {
+            ProjectileType::Bomb => 3,
+            _ => 1,
         };
-        audio::play(audio);
         Projectile {
             // Initialize all fields with default values
             hitbox: Hitbox {
                 x,
                 y,
-                w: 6,
-                h: 6,
+                w: size,
+                h: size,
             },
-            anim_key: random::u32().to_string(),
+            anim_key: rng.cosmetics.u32().to_string(),
             destroyed: false,
             collided: false,
+            timer: 0,
             velocity,
             angle,
-            damage: 1,
+            distance: 0.0,
+            damage,
+            pierce: 0,
+            hit_keys: vec![],
             projectile_type,
             projectile_owner,
+            source: None,
+            shape,
+            shrapnel,
+            beam: None,
+            fuse,
         }
     }
+
+    // Applies the stats of the ship that fired the projectile, resizing its hitbox around its center
+    pub fn apply_stats(&mut self, damage: u32, size: u32, pierce: u32) {
+        self.hitbox.x += (self.hitbox.w as f32 - size as f32) / 2.0;
+        self.hitbox.y += (self.hitbox.h as f32 - size as f32) / 2.0;
+        self.hitbox.w = size;
+        self.hitbox.h = size;
+        self.damage = damage;
+        self.pierce = pierce;
+    }
+
+    // Creates a Laser beam attached to the ship with the given key
+    // The beam is positioned relative to its anchor every frame, pointing up for the player and down for enemies
+    pub fn new_beam(anchor: &str, width: u32, warmup: u32, lifetime: u32, projectile_owner: ProjectileOwner, rng: &mut GameRng, platform: &mut dyn Platform) -> Self {
+        let angle = match projectile_owner {
+            ProjectileOwner::Enemy => 90.0,
+            ProjectileOwner::Player(_) => -90.0,
+        };
+        let mut projectile = Projectile::new(0.0, 0.0, 0.0, angle, ProjectileType::Laser, projectile_owner, rng, platform);
+        projectile.hitbox.w = width;
+        projectile.beam = Some(Beam {
+            anchor: anchor.to_string(),
+            warmup,
+            lifetime,
+            tick_rate: 8,
+        });
+        projectile
+    }
     // update is called once per frame within the [turbo::game] loop
-    pub fn update(&mut self, player: &mut Player, enemies: &mut Vec<Enemy>) {
-        let (screen_w, screen_h) = resolution();
+    // Returns any events for the GameState to handle, e.g. shrapnel spawned by a Fragment shell
+    pub fn update(&mut self, players: &mut [Player], enemies: &mut [Enemy], grid: &SpatialGrid, rng: &mut GameRng, platform: &mut dyn Platform) -> Vec<ProjectileEvent> {
+        let (screen_w, screen_h) = platform.resolution();
+        let mut events = vec![];
+
+        // Beams follow their anchor instead of travelling
+        if self.beam.is_some() {
+            self.update_beam(players, enemies, grid, platform);
 
         // If the projectile hasn't collided, update it as normal
-        if !self.collided {
+        } else if !self.collided {
             // update projectile position
             let radian_angle = self.angle.to_radians();
             self.hitbox.x += self.velocity * radian_angle.cos();
             self.hitbox.y += self.velocity * radian_angle.sin();
+            self.distance += self.velocity;
 
             // flag the projectile to be destroyed if it goes off screen
             if self.hitbox.y < -(self.hitbox.h as f32)
-            && self.hitbox.x < -(self.hitbox.w as f32)
-            && self.hitbox.x > screen_w as f32
-            && self.hitbox.y > screen_h as f32
+            || self.hitbox.x < -(self.hitbox.w as f32)
+            || self.hitbox.x > screen_w as f32
+            || self.hitbox.y > screen_h as f32
             {
                 self.destroyed = true;
             }
 
+            // Bombs are airborne until their fuse runs out, so they don't collide with anything
+            if let Some(fuse) = &mut self.fuse {
+                fuse.timer = fuse.timer.saturating_sub(1);
+                self.velocity *= 0.97; // slow down over the course of the arc
+                if fuse.timer == 0 {
+                    events = self.explode(players, enemies, grid, platform);
+                }
             // Checking for collisions with player or enemies based on projectile owner
-            match self.projectile_owner {
-                // Check collision with player
-                ProjectileOwner::Enemy => {
-                    if check_collision(&self.hitbox, &player.hitbox) 
-                    && player.hp > 0
-                    && player.hit_timer == 0 {
-                        player.take_damage(self.damage);
-                        audio::play("projectile_hit");
-                        self.collided = true;
+            } else {
+                let collider = self.collider();
+                match self.projectile_owner {
+                    // Check collision with the players, hitting the first one it reaches
+                    ProjectileOwner::Enemy => {
+                        let target = players.iter_mut().find(|player| {
+                            collider.overlaps(&Collider::new(&player.hitbox, Shape::Rect))
+                            && player.hp > 0
+                            && player.hit_timer == 0
+                        });
+                        if let Some(player) = target {
+                            // Splatter and Fragment deal their damage through the puddle or shrapnel instead
+                            if !self.bursts() {
+                                player.take_damage(self.damage, &self.source, platform);
+                            }
+                            events = self.hit(rng, platform);
+                        }
+                    }
+                    // Check collision with enemies
+                    ProjectileOwner::Player(shooter) => {
+                        for index in grid.query(&collider.bounds()) {
+                            let enemy = &mut enemies[index];
+                            if enemy.collides(&collider)
+                            && !enemy.destroyed
+                            && !self.hit_keys.contains(&enemy.key) {
+                                if !self.bursts() {
+                                    enemy.take_damage(&mut players[shooter], self.damage);
+                                }
+                                // Piercing projectiles keep going until they run out of pierce
+                                if self.pierce > 0 && !self.bursts() {
+                                    self.pierce -= 1;
+                                    self.hit_keys.push(enemy.key.clone());
+                                    platform.play_sound("projectile_hit");
+                                    continue;
+                                }
+                                events = self.hit(rng, platform);
+                                break; // Exit loop after first collision
+                            }
+                        }
                     }
                 }
-                // Check collision with enemies
-                ProjectileOwner::Player => {
-                    for enemy in enemies.iter_mut() {
-                        if check_collision(&self.hitbox, &enemy.hitbox) && !enemy.destroyed {
-                            enemy.take_damage(player, self.damage);
-                            
-                            audio::play("projectile_hit");
-                            self.collided = true;
-                            break; // Exit loop after first collision
+            }
+
+            // Fragment shells burst on their own once they reach their range
+            if let Some(shrapnel) = &self.shrapnel {
+                if !self.collided && self.distance >= shrapnel.range {
+                    events = self.hit(rng, platform);
+                }
+            }
+
+        // if a Splatter has collided, it lingers as a puddle damaging everything inside it
+        } else if self.projectile_type == ProjectileType::Splatter {
+            // deal damage every 10 frames, starting on the frame of impact
+            if self.timer.is_multiple_of(10) {
+                let collider = self.collider();
+                match self.projectile_owner {
+                    ProjectileOwner::Enemy => {
+                        for player in players.iter_mut() {
+                            if collider.overlaps(&Collider::new(&player.hitbox, Shape::Rect))
+                            && player.hp > 0
+                            && player.hit_timer == 0 {
+                                player.take_damage(self.damage, &self.source, platform);
+                            }
+                        }
+                    }
+                    ProjectileOwner::Player(shooter) => {
+                        for index in grid.query(&collider.bounds()) {
+                            let enemy = &mut enemies[index];
+                            if enemy.collides(&collider) && !enemy.destroyed {
+                                enemy.take_damage(&mut players[shooter], self.damage);
+                            }
                         }
                     }
                 }
             }
+            self.timer += 1;
+            // the puddle dries up after 1 second
+            if self.timer >= 60 {
+                self.destroyed = true;
+            }
 
         // if the projectile has collided, 
         } else {
-            // get reference to the SpriteAnimation of the projectile
-            let anim = animation::get(&self.anim_key);
-            // flag projectile as destroyed when the hit animation is done
-            if anim.done() {
+            self.timer += 1;
+            // flag projectile as destroyed once the hit animation has played out
+            // counted in frames rather than asking the SpriteAnimation, so it also works without turbo
+            if self.timer >= self.hit_duration() {
                 self.destroyed = true;
+                // stop the explosion's camera shake
+                if self.fuse.is_some() {
+                    platform.remove_shake();
+                }
             }
         }
+        events
+    }
+
+    // Keeps a Laser beam attached to its anchor and deals damage to everything in its path
+    fn update_beam(&mut self, players: &mut [Player], enemies: &mut [Enemy], grid: &SpatialGrid, platform: &mut dyn Platform) {
+        let (_, screen_h) = platform.resolution();
+        let Some(beam) = &mut self.beam else {
+            return;
+        };
+        // Find the hitbox of the ship the beam is attached to, the beam shuts off if it's gone
+        let anchor = match self.projectile_owner {
+            ProjectileOwner::Player(shooter) => players
+                .get(shooter)
+                .filter(|player| player.hp > 0)
+                .map(|player| player.hitbox.clone()),
+            ProjectileOwner::Enemy => enemies
+                .iter()
+                .find(|enemy| enemy.key == beam.anchor && !enemy.destroyed)
+                .map(|enemy| enemy.hitbox.clone()),
+        };
+        let Some(anchor) = anchor else {
+            self.destroyed = true;
+            return;
+        };
+        // Stretch the beam from the anchor to the edge of the screen
+        self.hitbox.x = anchor.x + (anchor.w as f32 - self.hitbox.w as f32) / 2.0;
+        match self.projectile_owner {
+            ProjectileOwner::Player(_) => {
+                self.hitbox.y = 0.0;
+                self.hitbox.h = anchor.y.max(0.0) as u32;
+            }
+            ProjectileOwner::Enemy => {
+                self.hitbox.y = anchor.y + anchor.h as f32;
+                self.hitbox.h = (screen_h as f32 - self.hitbox.y).max(0.0) as u32;
+            }
+        }
+
+        self.timer += 1;
+        // Beams are harmless while warming up
+        if self.timer <= beam.warmup {
+            return;
+        }
+        // Deal damage every few frames, starting on the first lethal frame
+        if (self.timer - beam.warmup - 1).is_multiple_of(beam.tick_rate) {
+            let collider = Collider::new(&self.hitbox, self.shape);
+            match self.projectile_owner {
+                ProjectileOwner::Enemy => {
+                    for player in players.iter_mut() {
+                        if collider.overlaps(&Collider::new(&player.hitbox, Shape::Rect))
+                        && player.hp > 0
+                        && player.hit_timer == 0 {
+                            player.take_damage(self.damage, &self.source, platform);
+                        }
+                    }
+                }
+                ProjectileOwner::Player(shooter) => {
+                    for index in grid.query(&collider.bounds()) {
+                        let enemy = &mut enemies[index];
+                        if enemy.collides(&collider) && !enemy.destroyed {
+                            enemy.take_damage(&mut players[shooter], self.damage);
+                        }
+                    }
+                }
+            }
+        }
+        // Shut the beam off when its lifetime runs out
+        beam.lifetime = beam.lifetime.saturating_sub(1);
+        if beam.lifetime == 0 {
+            self.destroyed = true;
+        }
+    }
+
+    // Detonates a Bomb, damaging everything in its blast radius with falloff towards the edge
+    fn explode(&mut self, players: &mut [Player], enemies: &mut [Enemy], grid: &SpatialGrid, platform: &mut dyn Platform) -> Vec<ProjectileEvent> {
+        let Some(fuse) = &self.fuse else {
+            return vec![];
+        };
+        let radius = fuse.radius;
+        let (cx, cy) = (
+            self.hitbox.x + self.hitbox.w as f32 / 2.0,
+            self.hitbox.y + self.hitbox.h as f32 / 2.0,
+        );
+        // square around the blast radius, used to find nearby enemies and as the hitbox of the explosion
+        let blast = Hitbox {
+            x: cx - radius,
+            y: cy - radius,
+            w: (radius * 2.0) as u32,
+            h: (radius * 2.0) as u32,
+        };
+        // damage scales down linearly with distance from the center, but always deals at least 1
+        let falloff = |distance: f32| {
+            ((self.damage as f32 * (1.0 - distance / radius)).ceil() as u32).max(1)
+        };
+        match self.projectile_owner {
+            ProjectileOwner::Enemy => {
+                for player in players.iter_mut() {
+                    if check_circle_collision(cx, cy, radius, &player.hitbox)
+                    && player.hp > 0
+                    && player.hit_timer == 0 {
+                        player.take_damage(falloff(distance_to_hitbox(cx, cy, &player.hitbox)), &self.source, platform);
+                    }
+                }
+            }
+            ProjectileOwner::Player(shooter) => {
+                for index in grid.query(&blast) {
+                    let enemy = &mut enemies[index];
+                    let distance = enemy.distance_to(cx, cy);
+                    if distance <= radius && !enemy.destroyed {
+                        enemy.take_damage(&mut players[shooter], falloff(distance));
+                    }
+                }
+            }
+        }
+        platform.play_sound("projectile_hit");
+        platform.shake_camera(3.0);
+        // the explosion animation covers the whole blast
+        self.collided = true;
+        self.hitbox = blast;
+        if fuse.clears_projectiles {
+            vec![ProjectileEvent::Blast {
+                x: cx,
+                y: cy,
+                radius,
+                owner: self.projectile_owner.clone(),
+            }]
+        } else {
+            vec![]
+        }
+    }
+
+    // Shape the projectile collides as this frame
+    // While flying it covers the whole distance travelled since the last frame, so fast projectiles can't pass through small enemies
+    pub fn collider(&self) -> Collider {
+        let collider = Collider::new(&self.hitbox, self.shape);
+        if self.collided || self.beam.is_some() {
+            return collider;
+        }
+        let radian_angle = self.angle.to_radians();
+        collider.swept(self.velocity * radian_angle.cos(), self.velocity * radian_angle.sin())
+    }
+
+    // Length of the hit animation in frames, matching the frame delays of the _hit sprites
+    fn hit_duration(&self) -> u32 {
+        match (&self.projectile_type, &self.projectile_owner) {
+            (ProjectileType::Bomb, _) => 25,
+            (_, ProjectileOwner::Player(_)) => 21,
+            (_, ProjectileOwner::Enemy) => 15,
+        }
+    }
+
+    // Whether this projectile deals its damage through an effect spawned on impact
+    fn bursts(&self) -> bool {
+        self.projectile_type == ProjectileType::Splatter || self.shrapnel.is_some()
+    }
+
+    // Flags the projectile as collided and sets up its impact effect
+    // Returns any projectiles spawned by the impact
+    fn hit(&mut self, rng: &mut GameRng, platform: &mut dyn Platform) -> Vec<ProjectileEvent> {
+        platform.play_sound("projectile_hit");
+        self.collided = true;
+        // Splatter bursts into a puddle centered on the point of impact
+        if self.projectile_type == ProjectileType::Splatter {
+            let (cx, cy) = (
+                self.hitbox.x + self.hitbox.w as f32 / 2.0,
+                self.hitbox.y + self.hitbox.h as f32 / 2.0,
+            );
+            self.hitbox = Hitbox {
+                x: cx - 12.0,
+                y: cy - 12.0,
+                w: 24,
+                h: 24,
+            };
+        }
+        // Fragment shells fan out into shrapnel centered on their heading
+        let mut shards = vec![];
+        if let Some(shrapnel) = &self.shrapnel {
+            let step = if shrapnel.count > 1 {
+                shrapnel.spread / (shrapnel.count - 1) as f32
+            } else {
+                0.0
+            };
+            let start = self.angle - step * (shrapnel.count.saturating_sub(1)) as f32 / 2.0;
+            for i in 0..shrapnel.count {
+                shards.push(ProjectileEvent::Spawn(Projectile {
+                    hitbox: Hitbox {
+                        x: self.hitbox.x + 1.0,
+                        y: self.hitbox.y + 1.0,
+                        w: 6,
+                        h: 6,
+                    },
+                    anim_key: rng.cosmetics.u32().to_string(),
+                    collided: false,
+                    destroyed: false,
+                    timer: 0,
+                    velocity: self.velocity * 1.25,
+                    angle: start + step * i as f32,
+                    distance: 0.0,
+                    damage: (self.damage / 2).max(1), // shrapnel deals reduced damage
+                    pierce: 0,
+                    hit_keys: vec![],
+                    projectile_owner: self.projectile_owner.clone(),
+                    projectile_type: ProjectileType::Basic,
+                    source: self.source.clone(),
+                    shape: Shape::Circle,
+                    shrapnel: None,
+                    beam: None,
+                    fuse: None,
+                }));
+            }
+        }
+        shards
     }
 
     pub fn draw(&self) {
         let owner = match self.projectile_owner {
             ProjectileOwner::Enemy => "enemy",
-            ProjectileOwner::Player => "player",
+            ProjectileOwner::Player(_) => "player",
+        };
+        // Beams are drawn as a thin warning line while warming up, then as a stretched sprite
+        if let Some(beam) = &self.beam {
+            let x = self.hitbox.x as i32 + self.hitbox.w as i32 / 2;
+            if self.timer <= beam.warmup {
+                // blink the warning line
+                if (self.timer / 4).is_multiple_of(2) {
+                    rect!(
+                        x = x,
+                        y = self.hitbox.y as i32,
+                        w = 1,
+                        h = self.hitbox.h,
+                        color = 0xff3344aa
+                    );
+                }
+            } else {
+                let anim = animation::get(&self.anim_key);
+                anim.use_sprite(&format!("projectiles/projectile_laser_{}", owner));
+                sprite!(
+                    animation_key = &self.anim_key,
+                    x = self.hitbox.x as i32,
+                    y = self.hitbox.y as i32,
+                    w = self.hitbox.w,
+                    h = self.hitbox.h,
+                );
+            }
+            return;
+        }
+        // Splatter and Fragment have their own sprites, other projectile types use the owner's
+        let name = match self.projectile_type {
+            ProjectileType::Splatter => "splatter",
+            ProjectileType::Fragment if !self.collided => "fragment",
+            ProjectileType::Bomb => "bomb",
+            _ => owner,
         };
         let anim = animation::get(&self.anim_key);
+        // Bombs in flight are drawn above their shadow, following an arc over the length of the fuse
+        if let Some(fuse) = self.fuse.as_ref().filter(|_| !self.collided) {
+            let progress = 1.0 - fuse.timer as f32 / fuse.length as f32;
+            let height = (progress * std::f32::consts::PI).sin() * 24.0;
+            circ!(
+                x = self.hitbox.x as i32 + 1,
+                y = self.hitbox.y as i32 + 2,
+                d = 6,
+                color = 0x00000088
+            );
+            anim.use_sprite("projectiles/projectile_bomb");
+            sprite!(
+                animation_key = &self.anim_key,
+                x = self.hitbox.x as i32,
+                y = (self.hitbox.y - height) as i32,
+                w = self.hitbox.w,
+                h = self.hitbox.h,
+            );
+            return;
+        }
         if !self.collided {
-            anim.use_sprite(&format!("projectiles/projectile_{}", owner));
+            anim.use_sprite(&format!("projectiles/projectile_{}", name));
+        } else if self.projectile_type == ProjectileType::Splatter {
+            // the puddle loops its animation until it dries up
+            anim.use_sprite("projectiles/projectile_splatter_hit");
         } else {
-            anim.use_sprite(&format!("projectiles/projectile_{}_hit", owner));
+            anim.use_sprite(&format!("projectiles/projectile_{}_hit", name));
             anim.set_repeat(0);
             anim.set_fill_forwards(true);
         }
-        
-        sprite!(animation_key = &self.anim_key, x = self.hitbox.x as i32, y = self.hitbox.y as i32);
+
+        // fade the puddle out over its last 20 frames
+        let opacity = if self.collided && self.projectile_type == ProjectileType::Splatter {
+            (60u32.saturating_sub(self.timer) as f32 / 20.0).min(1.0)
+        } else {
+            1.0
+        };
+        if !self.collided {
+            // projectiles in flight are stretched to fit their hitbox, which grows with the player's projectile size
+            sprite!(
+                animation_key = &self.anim_key,
+                x = self.hitbox.x as i32,
+                y = self.hitbox.y as i32,
+                w = self.hitbox.w,
+                h = self.hitbox.h,
+            );
+        } else {
+            sprite!(
+                animation_key = &self.anim_key,
+                x = self.hitbox.x as i32,
+                y = self.hitbox.y as i32,
+                opacity = opacity,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A tank, which has plenty of hp, at the given position
+    fn tank(x: f32, y: f32) -> Enemy {
+        let definitions = EnemyDefinition::load().unwrap();
+        let definition = definitions.iter().find(|definition| definition.name == "tank").unwrap();
+        let mut enemy = Enemy::new(definition, &mut GameRng::new(1), &MockPlatform::new(1, vec![]));
+        enemy.hitbox.x = x;
+        enemy.hitbox.y = y;
+        enemy
     }
-}
\ No newline at end of file
+
+    // Runs a frame of the projectile, with the enemy grid built the way the GameState builds it
+    fn step(projectile: &mut Projectile, players: &mut [Player], enemies: &mut [Enemy], platform: &mut MockPlatform) -> Vec<ProjectileEvent> {
+        let mut grid = SpatialGrid::new(platform);
+        grid.build(enemies.iter().map(|enemy| &enemy.hitbox));
+        projectile.update(players, enemies, &grid, &mut GameRng::new(1), platform)
+    }
+
+    #[test]
+    fn splatter_puddles_hurt_everything_inside_them_until_they_dry_up() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut players = [Player::new(0, &platform)];
+        // two tanks side by side, the glob flying up into the gap between them
+        let mut enemies = [tank(88.0, 100.0), tank(120.0, 100.0)];
+        let mut splatter = Projectile::new(116.0, 140.0, 4.0, -90.0, ProjectileType::Splatter, ProjectileOwner::Player(0), &mut GameRng::new(1), &mut platform);
+        while !splatter.collided {
+            step(&mut splatter, &mut players, &mut enemies, &mut platform);
+        }
+        // the glob itself deals no damage, the puddle does
+        assert!(enemies.iter().all(|enemy| enemy.hp == 10));
+        assert_eq!((splatter.hitbox.w, splatter.hitbox.h), (24, 24));
+        let mut frames = 0;
+        while !splatter.destroyed {
+            step(&mut splatter, &mut players, &mut enemies, &mut platform);
+            frames += 1;
+        }
+        // a tick every 10 frames for a second hits both tanks 6 times
+        assert_eq!(frames, 60);
+        assert!(enemies.iter().all(|enemy| enemy.hp == 4));
+    }
+
+    #[test]
+    fn fragment_shells_burst_into_weaker_shrapnel() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut players = [Player::new(0, &platform)];
+        let mut shell = Projectile::new(120.0, 400.0, 4.0, -90.0, ProjectileType::Fragment, ProjectileOwner::Player(0), &mut GameRng::new(1), &mut platform);
+        shell.apply_stats(4, 8, 0);
+        // with nothing in the way the shell bursts once it has flown its 160 pixel range
+        let mut frames = 0;
+        let events = loop {
+            frames += 1;
+            let events = step(&mut shell, &mut players, &mut [], &mut platform);
+            if shell.collided {
+                break events;
+            }
+        };
+        assert_eq!(frames, 40);
+        let shards: Vec<Projectile> = events
+            .into_iter()
+            .map(|event| match event {
+                ProjectileEvent::Spawn(shard) => shard,
+                ProjectileEvent::Blast { .. } => panic!("shells don't explode"),
+            })
+            .collect();
+        // 5 pieces across 90 degrees, centered on the shell's heading
+        let angles: Vec<f32> = shards.iter().map(|shard| shard.angle).collect();
+        assert_eq!(angles, [-135.0, -112.5, -90.0, -67.5, -45.0]);
+        assert!(shards.iter().all(|shard| shard.damage == 2 && shard.projectile_owner == ProjectileOwner::Player(0) && shard.shrapnel.is_none()));
+    }
+
+    #[test]
+    fn fragment_shells_only_hurt_through_their_shrapnel() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut players = [Player::new(0, &platform)];
+        let mut enemies = [tank(104.0, 100.0)];
+        let mut shell = Projectile::new(116.0, 140.0, 4.0, -90.0, ProjectileType::Fragment, ProjectileOwner::Player(0), &mut GameRng::new(1), &mut platform);
+        let mut events = vec![];
+        while !shell.collided {
+            events = step(&mut shell, &mut players, &mut enemies, &mut platform);
+        }
+        assert_eq!(enemies[0].hp, 10);
+        assert_eq!(events.len(), 5);
+    }
+
+    #[test]
+    fn beams_follow_their_ship_and_only_hurt_once_warmed_up() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut players = [Player::new(0, &platform)];
+        let mut enemies = [tank(112.0, 100.0)];
+        let mut beam = Projectile::new_beam(&player_key(0), 4, 10, 30, ProjectileOwner::Player(0), &mut GameRng::new(1), &mut platform);
+        for _ in 0..10 {
+            step(&mut beam, &mut players, &mut enemies, &mut platform);
+        }
+        // stretched from the top of the screen down to the middle of the ship
+        assert_eq!((beam.hitbox.x, beam.hitbox.y, beam.hitbox.h), (126.0, 0.0, 448));
+        assert_eq!(enemies[0].hp, 10);
+        // lethal for 30 frames, ticking every 8 starting on the first one
+        let mut frames = 0;
+        while !beam.destroyed {
+            step(&mut beam, &mut players, &mut enemies, &mut platform);
+            frames += 1;
+        }
+        assert_eq!(frames, 30);
+        assert_eq!(enemies[0].hp, 6);
+    }
+
+    #[test]
+    fn beams_shut_off_with_their_ship() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut players = [Player::new(0, &platform)];
+        let mut beam = Projectile::new_beam(&player_key(0), 4, 10, 30, ProjectileOwner::Player(0), &mut GameRng::new(1), &mut platform);
+        players[0].hitbox.x = 40.0;
+        step(&mut beam, &mut players, &mut [], &mut platform);
+        assert_eq!(beam.hitbox.x, 46.0);
+        players[0].hp = 0;
+        step(&mut beam, &mut players, &mut [], &mut platform);
+        assert!(beam.destroyed);
+    }
+
+    #[test]
+    fn bombs_explode_with_falloff_when_their_fuse_runs_out() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        let mut players = [Player::new(0, &platform)];
+        // one tank under the blast's center, one 20 pixels from it and one outside the 32 pixel radius
+        let mut enemies = [tank(84.0, 84.0), tank(120.0, 84.0), tank(140.0, 84.0)];
+        let mut bomb = Projectile::new(96.0, 96.0, 0.0, -90.0, ProjectileType::Bomb, ProjectileOwner::Player(0), &mut GameRng::new(1), &mut platform);
+        for _ in 0..59 {
+            assert!(step(&mut bomb, &mut players, &mut enemies, &mut platform).is_empty());
+        }
+        assert!(enemies.iter().all(|enemy| enemy.hp == 10));
+        let events = step(&mut bomb, &mut players, &mut enemies, &mut platform);
+        assert!(matches!(events[..], [ProjectileEvent::Blast { x: 100.0, y: 100.0, radius: 32.0, owner: ProjectileOwner::Player(0) }]));
+        let hp: Vec<u32> = enemies.iter().map(|enemy| enemy.hp).collect();
+        assert_eq!(hp, [7, 8, 10]);
+        assert_eq!(platform.shake, Some(3.0));
+        // the camera settles once the explosion has played out
+        while !bomb.destroyed {
+            step(&mut bomb, &mut players, &mut enemies, &mut platform);
+        }
+        assert_eq!(platform.shake, None);
+        assert_eq!(enemies[0].hp, 7);
+    }
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+// Recording of a run: the RNG seed it started with and every player's actions for every frame
+// A partner dropping in shows up as the frames getting longer, so they join at the same moment on playback
+// Playing the same actions back from the same seed reproduces the run exactly
+#[turbo::serialize]
+pub struct Replay {
+    pub seed: u32,
+    pub level: usize, // index of the level that was played
+    pub difficulty: Difficulty,
+    frames: Vec<(Vec<ActionFrame>, u32)>, // run-length encoded, each frame of actions, one per player, with the number of frames it was held
+    cursor: usize, // playback position in frames
+    repeat: u32, // how many frames of the actions at the cursor have been played back
+}
+
+impl Replay {
+    pub fn new(seed: u32, level: usize, difficulty: Difficulty) -> Self {
+        Replay {
+            seed,
+            level,
+            difficulty,
+            frames: vec![],
+            cursor: 0,
+            repeat: 0,
+        }
+    }
+
+    // Appends a frame of actions to the recording
+    pub fn record(&mut self, frame: &[ActionFrame]) {
+        match self.frames.last_mut() {
+            Some((last, count)) if last == frame => *count += 1,
+            _ => self.frames.push((frame.to_vec(), 1)),
+        }
+    }
+
+    // Returns the next frame of actions, or None once the recording is over
+    pub fn next_frame(&mut self) -> Option<Vec<ActionFrame>> {
+        let (frame, count) = self.frames.get(self.cursor)?;
+        let frame = frame.clone();
+        self.repeat += 1;
+        if self.repeat >= *count {
+            self.cursor += 1;
+            self.repeat = 0;
+        }
+        Some(frame)
+    }
+
+    // Starts playback over from the first frame
+    pub fn rewind(&mut self) {
+        self.cursor = 0;
+        self.repeat = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions(actions: &[Action]) -> ActionFrame {
+        let mut frame = ActionFrame::default();
+        for &action in actions {
+            frame.set(action, true);
+        }
+        frame
+    }
+
+    #[test]
+    fn frames_come_back_in_the_order_they_were_recorded() {
+        let mut replay = Replay::new(1, 0, Difficulty::Normal);
+        let recorded = [
+            vec![actions(&[Action::Fire])],
+            vec![actions(&[Action::Fire])],
+            vec![actions(&[Action::MoveLeft])],
+            vec![actions(&[Action::MoveLeft]), actions(&[Action::Fire])], // a partner joins
+            vec![actions(&[]), actions(&[])],
+        ];
+        for frame in &recorded {
+            replay.record(frame);
+        }
+        // the held frames are stored once
+        assert_eq!(replay.frames.len(), 4);
+        for _ in 0..2 {
+            let played: Vec<_> = std::iter::from_fn(|| replay.next_frame()).collect();
+            assert_eq!(played, recorded);
+            replay.rewind();
+        }
+    }
+}
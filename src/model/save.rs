@@ -0,0 +1,60 @@
+use super::*;
+
+// Everything kept between sessions, in the platform's local storage
+// Stored as JSON, with every field defaulted, so saves from older versions of the game still load
+#[turbo::serialize]
+#[derive(Default)]
+pub struct SaveData {
+    #[serde(default)]
+    pub high_scores: HighScores,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+impl SaveData {
+    // Reads the save from storage, starting over if there's none or it can't be read
+    pub fn load(platform: &mut dyn Platform) -> Self {
+        platform
+            .load_storage()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, platform: &mut dyn Platform) {
+        if let Ok(data) = serde_json::to_vec(self) {
+            platform.save_storage(&data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_survive_saving_and_loading() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        assert_eq!(SaveData::load(&mut platform).settings, Settings::default());
+        let mut save = SaveData::default();
+        save.settings.music_volume = 3;
+        save.settings.sfx_volume = 7;
+        save.settings.auto_fire = true;
+        save.settings.difficulty = Difficulty::Hard;
+        save.settings.bindings_mut(1).bind(Action::Fire, Button::X);
+        save.save(&mut platform);
+        assert_eq!(SaveData::load(&mut platform).settings, save.settings);
+    }
+
+    #[test]
+    fn old_or_broken_saves_still_load() {
+        let mut platform = MockPlatform::new(1, vec![]);
+        // from before the music volume and bindings were added
+        platform.storage = Some(br#"{ "settings": { "sfx_volume": 4 } }"#.to_vec());
+        let settings = SaveData::load(&mut platform).settings;
+        assert_eq!(settings.sfx_volume, 4);
+        assert_eq!(settings.music_volume, MAX_LEVEL);
+        assert_eq!(settings.bindings(0), Bindings::default());
+        platform.storage = Some(b"not json".to_vec());
+        assert_eq!(SaveData::load(&mut platform).settings, Settings::default());
+    }
+}
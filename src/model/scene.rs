@@ -0,0 +1,187 @@
+use super::*;
+
+// Length of a transition in frames, the scenes change halfway through once the screen is covered
+const TRANSITION_FRAMES: u32 = 30;
+
+// How the screen is covered and uncovered during a transition
+#[turbo::serialize]
+#[derive(PartialEq, Copy)]
+pub enum TransitionEffect {
+    Fade, // to black and back
+    Wipe, // a black curtain sweeping in from the left, then off to the right
+    Iris, // a circle closing in on the center of the screen, then opening back up
+}
+
+// A change to the scene stack
+#[turbo::serialize]
+#[derive(PartialEq, Copy)]
+pub enum SceneChange {
+    Push(Scene), // open a scene on top of the current one
+    Pop, // close the top scene, going back to the one under it
+    Replace(Scene), // swap the top scene for another
+    Reset, // start the whole game state over, on the menu
+    Retry, // start over, then go straight into a new run of the selected level
+}
+
+#[turbo::serialize]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    pub timer: u32, // frames since the transition started
+    pub change: Option<SceneChange>, // made halfway through, None once it has been
+}
+
+impl Transition {
+    // How much of the screen is covered, going from 0 to 1 and back to 0
+    pub fn cover(&self) -> f32 {
+        let half = TRANSITION_FRAMES as f32 / 2.0;
+        (1.0 - (self.timer as f32 - half).abs() / half).clamp(0.0, 1.0)
+    }
+
+    // Whether the screen is still being covered, i.e. the change hasn't been made yet
+    pub fn closing(&self) -> bool {
+        self.change.is_some()
+    }
+}
+
+// Scenes open on top of each other, and only the top one is updated
+// Overlays like the pause menu are drawn on top of the scene they were opened from, which stays visible
+#[turbo::serialize]
+pub struct SceneStack {
+    scenes: Vec<Scene>, // from the bottom up, never empty
+    pub transition: Option<Transition>, // running transition, if any
+}
+
+impl SceneStack {
+    pub fn new(scene: Scene) -> Self {
+        SceneStack {
+            scenes: vec![scene],
+            transition: None,
+        }
+    }
+
+    // The scene being updated
+    pub fn top(&self) -> Scene {
+        self.scenes[self.scenes.len() - 1]
+    }
+
+    // Scenes to draw, from the bottom up: the topmost scene that isn't an overlay and everything above it
+    pub fn visible(&self) -> &[Scene] {
+        let start = self.scenes.iter().rposition(|scene| !scene.is_overlay()).unwrap_or(0);
+        &self.scenes[start..]
+    }
+
+    pub fn contains(&self, scene: Scene) -> bool {
+        self.scenes.contains(&scene)
+    }
+
+    pub fn push(&mut self, scene: Scene) {
+        self.scenes.push(scene);
+    }
+
+    // The bottom scene is never closed
+    pub fn pop(&mut self) -> Option<Scene> {
+        if self.scenes.len() > 1 {
+            self.scenes.pop()
+        } else {
+            None
+        }
+    }
+
+    pub fn replace(&mut self, scene: Scene) {
+        let top = self.scenes.len() - 1;
+        self.scenes[top] = scene;
+    }
+
+    // Starts a transition, the change is made once the screen is covered
+    // Ignored while another transition is running, so a button held down can't queue up changes
+    pub fn transition(&mut self, effect: TransitionEffect, change: SceneChange) {
+        if self.transition.is_none() {
+            self.transition = Some(Transition {
+                effect,
+                timer: 0,
+                change: Some(change),
+            });
+        }
+    }
+
+    // Moves the running transition along by a frame
+    // Returns its change on the frame the screen is fully covered, for the game to make
+    pub fn update(&mut self) -> Option<SceneChange> {
+        let transition = self.transition.as_mut()?;
+        transition.timer += 1;
+        if transition.timer >= TRANSITION_FRAMES {
+            self.transition = None;
+            return None;
+        }
+        if transition.timer >= TRANSITION_FRAMES / 2 {
+            return transition.change.take();
+        }
+        None
+    }
+
+    // Whether the scenes are waiting for a transition to cover the screen, they aren't updated meanwhile
+    pub fn blocked(&self) -> bool {
+        self.transition.as_ref().is_some_and(|transition| transition.closing())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlays_show_the_scene_they_were_opened_from() {
+        let mut scenes = SceneStack::new(Scene::Game);
+        scenes.push(Scene::Paused);
+        scenes.push(Scene::Options);
+        assert_eq!(scenes.top(), Scene::Options);
+        assert_eq!(scenes.visible(), [Scene::Game, Scene::Paused, Scene::Options]);
+        assert_eq!(scenes.pop(), Some(Scene::Options));
+        assert_eq!(scenes.pop(), Some(Scene::Paused));
+        assert_eq!(scenes.top(), Scene::Game);
+        // the bottom scene stays open
+        assert_eq!(scenes.pop(), None);
+        scenes.replace(Scene::GameOver);
+        scenes.push(Scene::NameEntry);
+        assert_eq!(scenes.visible(), [Scene::GameOver, Scene::NameEntry]);
+        assert!(scenes.contains(Scene::GameOver) && !scenes.contains(Scene::Game));
+    }
+
+    #[test]
+    fn transitions_change_scenes_once_the_screen_is_covered() {
+        let mut scenes = SceneStack::new(Scene::Menu);
+        scenes.transition(TransitionEffect::Fade, SceneChange::Replace(Scene::Game));
+        // a second transition started meanwhile is ignored
+        scenes.transition(TransitionEffect::Iris, SceneChange::Reset);
+        let mut changes = vec![];
+        let mut covers = vec![];
+        for frame in 1..=TRANSITION_FRAMES {
+            if let Some(change) = scenes.update() {
+                changes.push((frame, change));
+                assert!(!scenes.blocked());
+            }
+            covers.extend(scenes.transition.as_ref().map(Transition::cover));
+        }
+        assert_eq!(changes, [(TRANSITION_FRAMES / 2, SceneChange::Replace(Scene::Game))]);
+        assert!(scenes.transition.is_none());
+        // the cover closes in, is complete on the frame of the change and opens back up
+        let half = (TRANSITION_FRAMES / 2) as usize;
+        assert!(covers[..half].windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(covers[half - 1], 1.0);
+        assert!(covers[half - 1..].windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn scenes_wait_for_a_transition_to_cover_the_screen() {
+        let mut scenes = SceneStack::new(Scene::Game);
+        assert!(!scenes.blocked());
+        scenes.transition(TransitionEffect::Wipe, SceneChange::Push(Scene::HighScores));
+        assert!(scenes.blocked());
+        for _ in 0..TRANSITION_FRAMES / 2 - 1 {
+            assert_eq!(scenes.update(), None);
+            assert!(scenes.blocked());
+        }
+        assert_eq!(scenes.update(), Some(SceneChange::Push(Scene::HighScores)));
+        assert!(!scenes.blocked());
+    }
+}
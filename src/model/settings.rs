@@ -0,0 +1,106 @@
+use super::*;
+
+// Highest level for the settings that go from 0 to a maximum, like volumes
+pub const MAX_LEVEL: u32 = 10;
+
+// Player preferences, kept in the save and applied as soon as they're changed
+// Missing fields are defaulted, so saves from before a setting was added still load
+#[turbo::serialize]
+#[derive(PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub music_volume: u32, // 0 to MAX_LEVEL, for sounds named music_*
+    pub sfx_volume: u32, // 0 to MAX_LEVEL, for every other sound
+    pub shake: u32, // 0 to MAX_LEVEL, how hard the camera shakes
+    pub hit_flash: u32, // 0 to MAX_LEVEL, how red ships flash when they're hit
+    pub auto_fire: bool, // fire without holding A
+    pub difficulty: Difficulty, // used from the next run on
+    pub bindings: Vec<Bindings>, // per gamepad, gamepads past the end of the list use the defaults
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_volume: MAX_LEVEL,
+            sfx_volume: MAX_LEVEL,
+            shake: MAX_LEVEL,
+            hit_flash: MAX_LEVEL,
+            auto_fire: false,
+            difficulty: Difficulty::Normal,
+            bindings: vec![Bindings::default()],
+        }
+    }
+}
+
+impl Settings {
+    // Volume for a sound, from 0 to 1
+    pub fn volume(&self, sound: &str) -> f32 {
+        let level = if sound.starts_with("music") { self.music_volume } else { self.sfx_volume };
+        level as f32 / MAX_LEVEL as f32
+    }
+
+    // Scale applied to camera shake, from 0 to 1
+    pub fn shake_scale(&self) -> f32 {
+        self.shake as f32 / MAX_LEVEL as f32
+    }
+
+    // Bindings for a gamepad
+    pub fn bindings(&self, gamepad: usize) -> Bindings {
+        self.bindings.get(gamepad).cloned().unwrap_or_default()
+    }
+
+    // Bindings for a gamepad to change, filling in defaults for any gamepads before it
+    pub fn bindings_mut(&mut self, gamepad: usize) -> &mut Bindings {
+        if self.bindings.len() <= gamepad {
+            self.bindings.resize_with(gamepad + 1, Bindings::default);
+        }
+        &mut self.bindings[gamepad]
+    }
+
+    // Color ships are tinted while flashing from a hit, plain white at 0 so they don't flash at all
+    pub fn hit_flash_color(&self) -> u32 {
+        let fade = 255 - 255 * self.hit_flash.min(MAX_LEVEL) / MAX_LEVEL;
+        0xff0000ff | fade << 16 | fade << 8
+    }
+}
+
+// How hard a run is, recorded with its replay so it plays back the same
+#[turbo::serialize]
+#[derive(PartialEq, Copy, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+        }
+    }
+
+    // HP the player starts a run with
+    pub fn player_hp(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 2,
+        }
+    }
+
+    // HP for an enemy defined with the given hp, never below 1
+    pub fn enemy_hp(&self, hp: u32) -> u32 {
+        let scale = match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        };
+        ((hp as f32 * scale).round() as u32).max(1)
+    }
+}
@@ -0,0 +1,43 @@
+use super::*;
+
+// Statistics collected over the course of a run, used by the balance simulator
+#[turbo::serialize]
+#[derive(Default)]
+pub struct RunStats {
+    pub frames: u32, // frames survived
+    pub score_curve: Vec<u32>, // score sampled every 10 seconds
+    pub kills: Vec<(String, u32)>, // enemies destroyed, by type
+    pub drops: Vec<(PowerupEffect, u32)>, // powerups spawned, by effect
+    pub pickups: Vec<(PowerupEffect, u32)>, // powerups collected, by effect
+    pub killed_by: Option<String>, // enemy type that dealt the final blow
+}
+
+impl RunStats {
+    // Called once per frame while the player is alive
+    pub fn update(&mut self, score: u32) {
+        self.frames += 1;
+        if self.frames.is_multiple_of(60 * 10) {
+            self.score_curve.push(score);
+        }
+    }
+
+    pub fn record_kill(&mut self, enemy_type: &String) {
+        tally(&mut self.kills, enemy_type);
+    }
+
+    pub fn record_drop(&mut self, effect: &PowerupEffect) {
+        tally(&mut self.drops, effect);
+    }
+
+    pub fn record_pickup(&mut self, effect: &PowerupEffect) {
+        tally(&mut self.pickups, effect);
+    }
+}
+
+// Increments the count for the given key, adding it to the list if it isn't there yet
+pub fn tally<T: PartialEq + Clone>(counts: &mut Vec<(T, u32)>, key: &T) {
+    match counts.iter_mut().find(|(k, _)| k == key) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((key.clone(), 1)),
+    }
+}
@@ -1,5 +1,3 @@
-use super::*;
-
 // convienent wrapper struct for rectangular hitboxes
 #[turbo::serialize]
 pub struct Hitbox {
@@ -9,22 +7,147 @@ pub struct Hitbox {
     pub h: u32,
 }
 
-// Function to check collision between two hitboxes
+// Function to check collision between two hitboxes, with float precision
+// For shapes other than rects, see Collider
 #[rustfmt::skip]
 pub fn check_collision(hitbox1: &Hitbox, hitbox2: &Hitbox) -> bool {
-    let x1 = hitbox1.x as i32;
-    let y1 = hitbox1.y as i32;
-    let w1 = hitbox1.w as i32;
-    let h1 = hitbox1.h as i32;
-    let x2 = hitbox2.x as i32;
-    let y2 = hitbox2.y as i32;
-    let w2 = hitbox2.w as i32;
-    let h2 = hitbox2.h as i32;
-    x1 < x2 + w2 && x1 + w1 > x2 &&
-    y1 < y2 + h2 && y1 + h1 > y2
-}
-
-// Pseudo-random number generator
-pub fn rand_with_seed(seed: u32) -> u32 {
-    (seed * 1103515245 + 12345) % 2147483648
+    hitbox1.x < hitbox2.x + hitbox2.w as f32 && hitbox1.x + hitbox1.w as f32 > hitbox2.x &&
+    hitbox1.y < hitbox2.y + hitbox2.h as f32 && hitbox1.y + hitbox1.h as f32 > hitbox2.y
+}
+
+// Function to check collision between a circle and a hitbox
+pub fn check_circle_collision(x: f32, y: f32, radius: f32, hitbox: &Hitbox) -> bool {
+    distance_to_hitbox(x, y, hitbox) <= radius
+}
+
+// Distance from a point to the closest point of a hitbox, 0 if the point is inside it
+pub fn distance_to_hitbox(x: f32, y: f32, hitbox: &Hitbox) -> f32 {
+    let dx = (hitbox.x - x).max(x - (hitbox.x + hitbox.w as f32)).max(0.0);
+    let dy = (hitbox.y - y).max(y - (hitbox.y + hitbox.h as f32)).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Stateless integer hash, safe for any input and well distributed
+// Used for cosmetic randomness that needs to be the same every time it's computed, like the starfield
+pub fn hash_u32(x: u32) -> u32 {
+    let mut x = x;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x
+}
+
+// Hash of two values, e.g. a seed and an index, or a pair of grid coordinates
+pub fn hash_2d(x: u32, y: u32) -> u32 {
+    hash_u32(x ^ hash_u32(y.wrapping_add(0x9e3779b9)))
+}
+
+// Maps a hash to a float between 0.0 (inclusive) and 1.0 (exclusive)
+fn hash_to_unit(hash: u32) -> f32 {
+    (hash >> 8) as f32 / (1 << 24) as f32
+}
+
+// Smooth 1D value noise between 0.0 and 1.0
+// Random values are placed at every integer x and smoothly interpolated in between
+pub fn value_noise_1d(x: f32, seed: u32) -> f32 {
+    let x0 = x.floor();
+    let t = x - x0;
+    let i = x0 as i32 as u32;
+    let a = hash_to_unit(hash_2d(seed, i));
+    let b = hash_to_unit(hash_2d(seed, i.wrapping_add(1)));
+    let t = t * t * (3.0 - 2.0 * t); // smoothstep to hide the grid
+    a + (b - a) * t
+}
+
+// Smooth 2D value noise between 0.0 and 1.0
+// Random values are placed at every integer grid point and smoothly interpolated in between
+pub fn value_noise_2d(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let (ix, iy) = (x0 as i32 as u32, y0 as i32 as u32);
+    let corner = |dx: u32, dy: u32| {
+        hash_to_unit(hash_2d(hash_2d(seed, ix.wrapping_add(dx)), iy.wrapping_add(dy)))
+    };
+    let tx = tx * tx * (3.0 - 2.0 * tx);
+    let ty = ty * ty * (3.0 - 2.0 * ty);
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+    top + (bottom - top) * ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lattice points and coordinates far from the origin, on both sides of it
+    const EXTREMES: [f32; 8] = [0.0, -0.5, -1.0, -12345.75, 1.0e6 + 0.5, -1.0e6 - 0.25, 1.0e30, -1.0e30];
+
+    #[test]
+    fn hashes_take_any_input() {
+        for x in [0, 1, u32::MAX - 1, u32::MAX] {
+            assert!(hash_to_unit(hash_u32(x)) < 1.0);
+            for y in [0, 1, u32::MAX - 1, u32::MAX] {
+                assert!(hash_to_unit(hash_2d(x, y)) < 1.0);
+            }
+        }
+        // the same input always hashes the same, and neighbours don't collide
+        assert_eq!(hash_u32(u32::MAX), hash_u32(u32::MAX));
+        assert_ne!(hash_u32(0), hash_u32(1));
+        assert_ne!(hash_2d(0, u32::MAX), hash_2d(u32::MAX, 0));
+    }
+
+    #[test]
+    fn noise_stays_between_0_and_1() {
+        for seed in [0, 1, u32::MAX] {
+            for &x in &EXTREMES {
+                for &y in &EXTREMES {
+                    let noise = value_noise_2d(x, y, seed);
+                    assert!((0.0..1.0).contains(&noise), "2d noise at ({}, {}) was {}", x, y, noise);
+                }
+                let noise = value_noise_1d(x, seed);
+                assert!((0.0..1.0).contains(&noise), "1d noise at {} was {}", x, noise);
+            }
+            // and everywhere in between lattice points
+            for i in 0..2000 {
+                let x = i as f32 * 0.037 - 37.0;
+                let y = i as f32 * -0.053 + 11.0;
+                assert!((0.0..1.0).contains(&value_noise_1d(x, seed)));
+                assert!((0.0..1.0).contains(&value_noise_2d(x, y, seed)));
+            }
+        }
+    }
+
+    #[test]
+    fn noise_is_continuous_across_lattice_points() {
+        let epsilon = 1.0e-3;
+        for n in -20..20 {
+            let n = n as f32;
+            let at = value_noise_1d(n, 7);
+            assert!((value_noise_1d(n - epsilon, 7) - at).abs() < 0.01, "1d jump at {}", n);
+            assert!((value_noise_1d(n + epsilon, 7) - at).abs() < 0.01, "1d jump at {}", n);
+            for y in [-3.5, 0.0, 2.25] {
+                let at = value_noise_2d(n, y, 7);
+                assert!((value_noise_2d(n - epsilon, y, 7) - at).abs() < 0.01, "2d jump at ({}, {})", n, y);
+                assert!((value_noise_2d(n + epsilon, y, 7) - at).abs() < 0.01, "2d jump at ({}, {})", n, y);
+                let at = value_noise_2d(y, n, 7);
+                assert!((value_noise_2d(y, n - epsilon, 7) - at).abs() < 0.01, "2d jump at ({}, {})", y, n);
+                assert!((value_noise_2d(y, n + epsilon, 7) - at).abs() < 0.01, "2d jump at ({}, {})", y, n);
+            }
+        }
+    }
+
+    #[test]
+    fn hashes_spread_evenly() {
+        // sequential inputs, like star indices, land evenly across 10 buckets
+        let samples = 100_000;
+        let mut buckets = [0u32; 10];
+        for i in 0..samples {
+            buckets[(hash_to_unit(hash_u32(i)) * 10.0) as usize] += 1;
+        }
+        for (i, &count) in buckets.iter().enumerate() {
+            assert!((9_000..=11_000).contains(&count), "bucket {} got {} of {}", i, count, samples);
+        }
+    }
 }
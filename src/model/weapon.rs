@@ -0,0 +1,120 @@
+use super::*;
+
+// Struct for a weapon in the player's inventory
+#[turbo::serialize]
+pub struct Weapon {
+    pub projectile_type: ProjectileType,
+    pub cooldown: f32, // time between shots, as a multiple of the player's rate of fire
+    pub ammo: Option<u32>, // shots left (frames of beam for Lasers), None for unlimited
+}
+
+impl Weapon {
+    // Initialize different weapons with different properties
+    pub fn new(projectile_type: ProjectileType) -> Self {
+        let (cooldown, ammo) = match projectile_type {
+            ProjectileType::Basic => (1.0, None),
+            ProjectileType::Splatter => (1.5, Some(30)),
+            ProjectileType::Fragment => (2.0, Some(20)),
+            ProjectileType::Laser => (0.0, Some(300)), // 5 seconds of beam
+            ProjectileType::Bomb => (3.0, Some(8)),
+        };
+        Weapon {
+            projectile_type,
+            cooldown,
+            ammo,
+        }
+    }
+
+    // Maximum ammo for a fresh weapon of this type, used when refilling
+    pub fn max_ammo(&self) -> Option<u32> {
+        Weapon::new(self.projectile_type.clone()).ammo
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.projectile_type {
+            ProjectileType::Basic => "BLASTER",
+            ProjectileType::Splatter => "SPLATTER",
+            ProjectileType::Fragment => "FRAGMENT",
+            ProjectileType::Laser => "LASER",
+            ProjectileType::Bomb => "BOMB",
+        }
+    }
+
+    // Fires the weapon from the given ship hitbox using this weapon's fire pattern
+    // Projectile speed, damage, size and pierce are derived from the player's stats, shooter is the player's index
+    #[allow(clippy::too_many_arguments)]
+    pub fn fire(&mut self, shooter: usize, hitbox: &Hitbox, stats: &PlayerStats, projectiles: &mut Vec<Projectile>, rng: &mut GameRng, platform: &mut dyn Platform) {
+        let speed = stats.projectile_speed as f32;
+        // extra size on top of the default 6px shot, added to bigger projectiles too
+        let size_bonus = stats.projectile_size.saturating_sub(6);
+        match self.projectile_type {
+            // Twin shots from the wings
+            ProjectileType::Basic => {
+                for i in 0..=1 {
+                    let mut projectile = Projectile::new(
+                        hitbox.x + i as f32 * 13.0,
+                        hitbox.y - 8.0,
+                        speed,
+                        -90.0,
+                        ProjectileType::Basic,
+                        ProjectileOwner::Player(shooter),
+                        rng,
+                        platform,
+                    );
+                    projectile.apply_stats(stats.damage, stats.projectile_size, stats.pierce);
+                    projectiles.push(projectile);
+                }
+            }
+            // A single slower shot from the nose of the ship
+            ProjectileType::Splatter | ProjectileType::Fragment | ProjectileType::Bomb => {
+                let mut projectile = Projectile::new(
+                    hitbox.x + hitbox.w as f32 / 2.0 - 4.0,
+                    hitbox.y - 8.0,
+                    speed * 0.6,
+                    -90.0,
+                    self.projectile_type.clone(),
+                    ProjectileOwner::Player(shooter),
+                    rng,
+                    platform,
+                );
+                // Bombs hit three times as hard, and only Basic shots can pierce
+                let damage = match self.projectile_type {
+                    ProjectileType::Bomb => stats.damage * 3,
+                    _ => stats.damage,
+                };
+                let size = projectile.hitbox.w + size_bonus;
+                projectile.apply_stats(damage, size, 0);
+                projectiles.push(projectile);
+            }
+            // A continuous beam that stays on while the button is held
+            ProjectileType::Laser => {
+                let anchor = player_key(shooter);
+                let beam = projectiles
+                    .iter_mut()
+                    .find(|projectile| projectile.beam.as_ref().is_some_and(|beam| beam.anchor == anchor));
+                match beam {
+                    // keep the current beam alive for another frame, picking up any stat changes
+                    Some(projectile) => {
+                        projectile.damage = stats.damage;
+                        if let Some(beam) = &mut projectile.beam {
+                            beam.lifetime = beam.lifetime.max(2);
+                        }
+                    }
+                    None => {
+                        let mut projectile = Projectile::new_beam(&anchor, 4 + size_bonus, 6, 2, ProjectileOwner::Player(shooter), rng, platform);
+                        projectile.damage = stats.damage;
+                        projectiles.push(projectile);
+                    }
+                }
+            }
+        }
+        // use up ammo
+        if let Some(ammo) = &mut self.ammo {
+            *ammo = ammo.saturating_sub(1);
+        }
+    }
+
+    pub fn out_of_ammo(&self) -> bool {
+        self.ammo == Some(0)
+    }
+}